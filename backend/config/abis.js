@@ -1,13 +1,15 @@
 // TokenFactory ABI (Stylus contract)
 const FACTORY_ABI = [
-  "function createToken(bytes32 name, bytes32 symbol, uint256 decimals, uint256 initial_supply) external returns (uint256)",
+  "function createToken(bytes32 name, bytes32 symbol, uint256 decimals, uint256 initial_supply, uint8 token_type, uint32 features) external returns (uint256)",
+  "function createTokenWithSalt(bytes32 name, bytes32 symbol, uint256 decimals, uint256 initial_supply, bytes32 salt, uint8 token_type, uint32 features) external returns (uint256)",
+  "function tokenIdFor(address creator, bytes32 salt) external view returns (uint256)",
   "function getTokenCount() external view returns (uint256)",
+  "event TokenCreated(address indexed creator, uint256 indexed token_id, bytes32 name, bytes32 symbol, uint256 decimals, uint256 initial_supply)",
   "function getTokenInfo(uint256 token_id) external view returns (bytes32 name, bytes32 symbol, uint256 decimals, uint256 totalSupply, address creator)"
 ];
 
 // NFTFactory ABI (Stylus contract)
 const NFT_FACTORY_ABI = [
-  "function initialize(address implementation) external",
   "function create_collection(string name, string symbol, string base_uri) external returns (address)",
   "function get_implementation() external view returns (address)",
   "function get_collection_count() external view returns (uint256)",
@@ -27,7 +29,9 @@ const ERC20_TOKEN_ABI = [
   "function approve(uint256 token_id, address spender, uint256 amount) external returns (bool)",
   "function transferFrom(uint256 token_id, address from, address to, uint256 amount) external returns (bool)",
   "function getTokenInfo(uint256 token_id) external view returns (bytes32 name, bytes32 symbol, uint256 decimals, uint256 totalSupply, address creator)",
-  "function getTokenCount() external view returns (uint256)"
+  "function getTokenCount() external view returns (uint256)",
+  "event Transfer(uint256 indexed token_id, address indexed from, address indexed to, uint256 value)",
+  "event Approval(uint256 indexed token_id, address indexed owner, address indexed spender, uint256 value)"
 ];
 
 // ERC721 Collection ABI (Stylus contract)
@@ -55,11 +55,17 @@ async function deployToken(req, res) {
     const decimalsBigInt = BigInt(decimals.toString());
     const initialSupplyBigInt = BigInt(initialSupply.toString());
 
+    // Random salt so the new token id can be resolved without racing other creators
+    const salt = ethers.hexlify(ethers.randomBytes(32));
+    // Standard (non-rebasing) token without optional features
+    const tokenType = 0;
+    const features = 0;
+
     // Estimate gas
     let gasEstimate;
     let estimatedCost = null;
     try {
-      gasEstimate = await factory.createToken.estimateGas(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt);
+      gasEstimate = await factory.createTokenWithSalt.estimateGas(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt, salt, tokenType, features);
       
       const feeData = await provider.getFeeData();
       if (feeData.gasPrice) {
@@ -84,8 +90,8 @@ async function deployToken(req, res) {
 
     // Create token
     const tx = gasEstimate 
-      ? await factory.createToken(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt, { gasLimit: gasEstimate * 12n / 10n })
-      : await factory.createToken(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt);
+      ? await factory.createTokenWithSalt(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt, salt, tokenType, features, { gasLimit: gasEstimate * 12n / 10n })
+      : await factory.createTokenWithSalt(nameBytes32, symbolBytes32, decimalsBigInt, initialSupplyBigInt, salt, tokenType, features);
 
     console.log('Transaction sent:', tx.hash);
 
@@ -93,11 +99,8 @@ async function deployToken(req, res) {
     const receipt = await tx.wait();
     console.log('Transaction confirmed in block:', receipt.blockNumber);
 
-    // The createToken function returns the token_id
-    // We need to decode it from the transaction result
-    const tokenId = receipt.logs && receipt.logs.length > 0 
-      ? await factory.getTokenCount() - 1n // Get the last created token ID
-      : 0n;
+    // Resolve the token id registered under (creator, salt)
+    const tokenId = await factory.tokenIdFor(wallet.address, salt);
     
     console.log('Token created with ID:', tokenId.toString());
 
@@ -17,7 +17,6 @@ const FACTORY_ADDRESS = process.env.TOKEN_FACTORY_ADDRESS || '0x0000000000000000
 
 // TokenFactory ABI (Stylus contract)
 const FACTORY_ABI = [
-  "function initialize(address implementation) external",
   "function create_token(string name, string symbol, uint256 decimals, uint256 initial_supply) external returns (address)",
   "function get_implementation() external view returns (address)",
   "function get_token_count() external view returns (uint256)",
@@ -33,7 +32,6 @@ const NFT_FACTORY_ADDRESS = process.env.NFT_FACTORY_ADDRESS || '0x00000000000000
 
 // NFTFactory ABI (Stylus contract)
 const NFT_FACTORY_ABI = [
-  "function initialize(address implementation) external",
   "function create_collection(string name, string symbol, string base_uri) external returns (address)",
   "function get_implementation() external view returns (address)",
   "function get_collection_count() external view returns (uint256)",
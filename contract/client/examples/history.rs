@@ -0,0 +1,60 @@
+//! Prints every token and collection created through the factories, read from their events.
+//!
+//! Expects these env vars (or a `.env` file):
+//! RPC_URL, TOKEN_FACTORY_ADDRESS, NFT_FACTORY_ADDRESS and optionally FROM_BLOCK.
+
+use dotenv::dotenv;
+use ethers::{
+    providers::{Http, Provider},
+    types::Address,
+};
+use eyre::eyre;
+use factory_client::{names, NftFactory, TokenFactory, TokenFactoryEvents};
+use std::sync::Arc;
+
+fn env(name: &str) -> eyre::Result<String> {
+    std::env::var(name).map_err(|_| eyre!("No {} env var set", name))
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    dotenv().ok();
+    let provider = Arc::new(Provider::<Http>::try_from(env("RPC_URL")?)?);
+    let token_factory_address: Address = env("TOKEN_FACTORY_ADDRESS")?.parse()?;
+    let nft_factory_address: Address = env("NFT_FACTORY_ADDRESS")?.parse()?;
+    let from_block: u64 = std::env::var("FROM_BLOCK").unwrap_or_else(|_| "0".into()).parse()?;
+
+    let factory = TokenFactory::new(token_factory_address, provider.clone());
+    for event in factory.events().from_block(from_block).query().await? {
+        match event {
+            TokenFactoryEvents::TokenCreatedFilter(created) => println!(
+                "token {} {} ({}) by {:?}, supply {}",
+                created.token_id,
+                names::unpack_bytes32(&created.name),
+                names::unpack_bytes32(&created.symbol),
+                created.creator,
+                created.initial_supply,
+            ),
+            TokenFactoryEvents::TransferFilter(transfer) => println!(
+                "  token {}: {} from {:?} to {:?}",
+                transfer.token_id, transfer.value, transfer.from, transfer.to,
+            ),
+            _ => {}
+        }
+    }
+
+    let nft_factory = NftFactory::new(nft_factory_address, provider);
+    let collections = nft_factory
+        .collection_created_filter()
+        .from_block(from_block)
+        .query()
+        .await?;
+    for created in collections {
+        println!(
+            "collection {} {} ({}) at {:?} by {:?}",
+            created.collection_id, created.name, created.symbol, created.collection_address, created.creator,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Runs the full token and NFT lifecycle against deployed factories on a local dev node.
+//! It creates a factory token, resolves its id from the creator salt, transfers and
+//! approves it, shows how a failing transfer decodes into `InsufficientBalance`, then
+//! creates an NFT collection, mints into it and transfers the NFT.
+//!
+//! Expects a local Nitro dev node (http://localhost:8547) with both factories deployed
+//! and the NftFactory constructed, and these env vars (or a `.env` file):
+//! RPC_URL, PRIV_KEY_PATH, TOKEN_FACTORY_ADDRESS, NFT_FACTORY_ADDRESS
+//! and optionally RECIPIENT_ADDRESS.
+
+use dotenv::dotenv;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use eyre::eyre;
+use factory_client::{errors, events, names, token_info, Erc721, NftFactory, TokenFactory, TokenFactoryErrors};
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Your private key file path.
+const PRIV_KEY_PATH: &str = "PRIV_KEY_PATH";
+
+/// Stylus RPC endpoint url.
+const RPC_URL: &str = "RPC_URL";
+
+/// Deployed TokenFactory address.
+const TOKEN_FACTORY_ADDRESS: &str = "TOKEN_FACTORY_ADDRESS";
+
+/// Deployed NftFactory address.
+const NFT_FACTORY_ADDRESS: &str = "NFT_FACTORY_ADDRESS";
+
+/// Account receiving tokens and NFTs, defaults to the second dev account.
+const RECIPIENT_ADDRESS: &str = "RECIPIENT_ADDRESS";
+
+fn env(name: &str) -> eyre::Result<String> {
+    std::env::var(name).map_err(|_| eyre!("No {} env var set", name))
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    dotenv().ok();
+    let priv_key_path = env(PRIV_KEY_PATH)?;
+    let provider = Provider::<Http>::try_from(env(RPC_URL)?)?;
+    let token_factory_address: Address = env(TOKEN_FACTORY_ADDRESS)?.parse()?;
+    let nft_factory_address: Address = env(NFT_FACTORY_ADDRESS)?.parse()?;
+    let recipient: Address = std::env::var(RECIPIENT_ADDRESS)
+        .unwrap_or_else(|_| "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string())
+        .parse()?;
+
+    let privkey = read_secret_from_file(&priv_key_path)?;
+    let wallet = LocalWallet::from_str(&privkey)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let client = Arc::new(SignerMiddleware::new(
+        provider,
+        wallet.clone().with_chain_id(chain_id),
+    ));
+    let me = wallet.address();
+
+    // ---- ERC20 tokens ----
+    let factory = TokenFactory::new(token_factory_address, client.clone());
+
+    let salt: [u8; 32] = rand_salt();
+    let receipt = factory
+        .create_token_with_salt(
+            names::pack_bytes32("Lifecycle Token")?,
+            names::pack_bytes32("LIFE")?,
+            U256::from(18),
+            U256::exp10(24),
+            salt,
+            0, // A standard (non-rebasing) token
+            0, // With no optional features
+        )
+        .send()
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("createTokenWithSalt was dropped"))?;
+
+    let token_id = factory.token_id_for(me, salt).call().await?;
+    let created = events::created_tokens(&receipt, token_factory_address);
+    println!("Created token {token_id} (event: {:?})", created.first().map(|e| e.token_id));
+    println!("Token info = {:?}", token_info(&factory, token_id).await?);
+
+    factory
+        .transfer(token_id, recipient, U256::exp10(21))
+        .send()
+        .await?
+        .await?;
+    println!(
+        "Transferred 1000 LIFE, balances: me = {}, recipient = {}",
+        factory.balance_of(token_id, me).call().await?,
+        factory.balance_of(token_id, recipient).call().await?,
+    );
+
+    factory
+        .approve(token_id, recipient, U256::exp10(20))
+        .send()
+        .await?
+        .await?;
+    println!("Allowance = {}", factory.allowance(token_id, me, recipient).call().await?);
+
+    // Custom errors come back typed
+    let too_much = U256::exp10(30);
+    match factory.transfer(token_id, recipient, too_much).call().await {
+        Err(err) => match errors::token_factory_error(&err) {
+            Some(TokenFactoryErrors::InsufficientBalance(e)) => {
+                println!("Transfer rejected as expected: have {}, want {}", e.have, e.want)
+            }
+            _ => return Err(err.into()),
+        },
+        Ok(_) => return Err(eyre!("oversized transfer unexpectedly succeeded")),
+    }
+
+    // ---- NFT collections ----
+    let nft_factory = NftFactory::new(nft_factory_address, client.clone());
+
+    let receipt = nft_factory
+        .create_collection("Lifecycle Art".into(), "LART".into(), "ipfs://lifecycle/".into())
+        .send()
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("createCollection was dropped"))?;
+    let collection_address = events::created_collection(&receipt, nft_factory_address)
+        .ok_or_else(|| eyre!("no CollectionCreated event"))?
+        .collection_address;
+    println!("Created collection at {collection_address:?}");
+
+    let collection = Erc721::new(collection_address, client.clone());
+    let receipt = collection
+        .mint(me)
+        .send()
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("mint was dropped"))?;
+    let nft_id = *events::minted_nfts(&receipt, collection_address)
+        .first()
+        .ok_or_else(|| eyre!("no mint Transfer event"))?;
+    println!("Minted NFT {nft_id}: {}", collection.token_uri(nft_id).call().await?);
+
+    collection
+        .transfer_from(me, recipient, nft_id)
+        .send()
+        .await?
+        .await?;
+    println!("NFT {nft_id} is now owned by {:?}", collection.owner_of(nft_id).call().await?);
+
+    Ok(())
+}
+
+/// A salt that is unique enough for a demo run
+fn rand_salt() -> [u8; 32] {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut salt = [0u8; 32];
+    salt[16..].copy_from_slice(&nanos.to_be_bytes());
+    salt
+}
+
+fn read_secret_from_file(fpath: &str) -> eyre::Result<String> {
+    let f = std::fs::File::open(fpath)?;
+    let mut buf_reader = BufReader::new(f);
+    let mut secret = String::new();
+    buf_reader.read_line(&mut secret)?;
+    Ok(secret.trim().to_string())
+}
@@ -0,0 +1,13 @@
+//! Contract bindings generated from the exported ABIs
+
+#![allow(clippy::all)]
+
+mod generated {
+    use ethers::contract::abigen;
+
+    abigen!(TokenFactory, "../token_factory/token_abi.json");
+    abigen!(NftFactory, "../nft_factory/nft_abi.json");
+    abigen!(Erc721, "../nft_factory/erc721_abi.json");
+}
+
+pub use generated::{erc_721 as erc721, nft_factory, token_factory};
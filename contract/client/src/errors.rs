@@ -0,0 +1,73 @@
+//! Decoding reverts into the contracts' custom errors
+//!
+//! ```ignore
+//! match factory.transfer(token_id, to, amount).send().await {
+//!     Err(err) => match errors::token_factory_error(&err) {
+//!         Some(TokenFactoryErrors::InsufficientBalance(e)) => println!("only {} available", e.have),
+//!         _ => return Err(err.into()),
+//!     },
+//!     Ok(pending) => { /* ... */ }
+//! }
+//! ```
+
+use ethers::{abi::AbiDecode, contract::ContractError, providers::Middleware};
+
+use crate::{Erc721Errors, NftFactoryErrors, TokenFactoryErrors};
+
+/// Decodes a TokenFactory revert, `None` if `err` is not one of its custom errors
+pub fn token_factory_error<M: Middleware>(err: &ContractError<M>) -> Option<TokenFactoryErrors> {
+    err.decode_contract_revert()
+}
+
+/// Decodes an NftFactory revert, `None` if `err` is not one of its custom errors
+pub fn nft_factory_error<M: Middleware>(err: &ContractError<M>) -> Option<NftFactoryErrors> {
+    err.decode_contract_revert()
+}
+
+/// Decodes an ERC721 collection revert, `None` if `err` is not one of its custom errors
+pub fn erc721_error<M: Middleware>(err: &ContractError<M>) -> Option<Erc721Errors> {
+    err.decode_contract_revert()
+}
+
+/// Decodes raw revert data (selector included), e.g. from a trace or a forwarder result
+pub fn decode_revert_data<E: AbiDecode>(data: impl AsRef<[u8]>) -> Option<E> {
+    E::decode(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::{erc721::NotOwnerOrApproved, token_factory::InsufficientBalance};
+    use ethers::{
+        abi::AbiEncode,
+        types::{Address, U256},
+    };
+
+    #[test]
+    fn test_decode_insufficient_balance() {
+        let error = InsufficientBalance {
+            from: Address::repeat_byte(1),
+            have: U256::from(5),
+            want: U256::from(7),
+        };
+
+        let decoded = decode_revert_data::<TokenFactoryErrors>(error.clone().encode());
+        assert_eq!(decoded, Some(TokenFactoryErrors::InsufficientBalance(error)));
+    }
+
+    #[test]
+    fn test_decode_not_owner_or_approved() {
+        let error = NotOwnerOrApproved {
+            caller: Address::repeat_byte(2),
+            token_id: U256::from(1),
+        };
+
+        let decoded = decode_revert_data::<Erc721Errors>(error.clone().encode());
+        assert_eq!(decoded, Some(Erc721Errors::NotOwnerOrApproved(error)));
+    }
+
+    #[test]
+    fn test_unknown_revert() {
+        assert_eq!(decode_revert_data::<TokenFactoryErrors>([0xde, 0xad, 0xbe, 0xef]), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Extracting events from transaction receipts
+//!
+//! For historical queries use the generated filters instead, e.g.
+//! `factory.token_created_filter().from_block(start).query().await`.
+
+use ethers::{
+    contract::{parse_log, EthLogDecode},
+    types::{Address, TransactionReceipt, U256},
+};
+
+use crate::bindings::{
+    erc721::TransferFilter as NftTransferFilter,
+    nft_factory::CollectionCreatedFilter,
+    token_factory::TokenCreatedFilter,
+};
+
+/// Decodes every log of type `E` that `emitter` emitted in `receipt`
+pub fn events_in<E: EthLogDecode>(receipt: &TransactionReceipt, emitter: Address) -> Vec<E> {
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == emitter)
+        .filter_map(|log| parse_log::<E>(log.clone()).ok())
+        .collect()
+}
+
+/// Returns the tokens created by a `createToken` / `createTokenWithSalt` / `createTokens` transaction
+pub fn created_tokens(receipt: &TransactionReceipt, factory: Address) -> Vec<TokenCreatedFilter> {
+    events_in(receipt, factory)
+}
+
+/// Returns the collection created by a `createCollection` transaction
+pub fn created_collection(receipt: &TransactionReceipt, factory: Address) -> Option<CollectionCreatedFilter> {
+    events_in(receipt, factory).into_iter().next()
+}
+
+/// Returns the ids of the NFTs minted by a transaction in `collection`
+pub fn minted_nfts(receipt: &TransactionReceipt, collection: Address) -> Vec<U256> {
+    events_in::<NftTransferFilter>(receipt, collection)
+        .into_iter()
+        .filter(|transfer| transfer.from == Address::zero())
+        .map(|transfer| transfer.token_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::names::pack_bytes32;
+    use ethers::{
+        abi::{encode, Token},
+        contract::EthEvent,
+        types::{Log, H256},
+    };
+
+    fn token_created_log(factory: Address, creator: Address, token_id: u64) -> Log {
+        Log {
+            address: factory,
+            topics: vec![
+                TokenCreatedFilter::signature(),
+                H256::from(creator),
+                H256::from_low_u64_be(token_id),
+            ],
+            data: encode(&[
+                Token::FixedBytes(pack_bytes32("MyToken").unwrap().to_vec()),
+                Token::FixedBytes(pack_bytes32("MTK").unwrap().to_vec()),
+                Token::Uint(U256::from(18)),
+                Token::Uint(U256::from(1_000)),
+            ])
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_created_tokens() {
+        let factory = Address::repeat_byte(0xfa);
+        let creator = Address::repeat_byte(0xc0);
+        let receipt = TransactionReceipt {
+            logs: vec![
+                token_created_log(factory, creator, 3),
+                // Same event from another contract is ignored
+                token_created_log(Address::repeat_byte(0xee), creator, 9),
+            ],
+            ..Default::default()
+        };
+
+        let created = created_tokens(&receipt, factory);
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].token_id, U256::from(3));
+        assert_eq!(created[0].creator, creator);
+        assert_eq!(created[0].decimals, U256::from(18));
+    }
+}
@@ -0,0 +1,56 @@
+//! `bytes32` packing for token names and symbols
+//!
+//! The TokenFactory stores names and symbols as right-padded UTF-8 in a
+//! `bytes32`, the same layout as `ethers.encodeBytes32String`.
+
+use std::fmt;
+
+/// A string that does not fit in 32 bytes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameTooLong(pub String);
+
+impl fmt::Display for NameTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\" is {} bytes, at most 32 fit in a bytes32", self.0, self.0.len())
+    }
+}
+
+impl std::error::Error for NameTooLong {}
+
+/// Packs `value` into a right-padded `bytes32`
+pub fn pack_bytes32(value: &str) -> Result<[u8; 32], NameTooLong> {
+    let bytes = value.as_bytes();
+    if bytes.len() > 32 {
+        return Err(NameTooLong(value.to_string()));
+    }
+    let mut packed = [0u8; 32];
+    packed[..bytes.len()].copy_from_slice(bytes);
+    Ok(packed)
+}
+
+/// Unpacks a right-padded `bytes32` into a string, replacing invalid UTF-8
+pub fn unpack_bytes32(value: &[u8; 32]) -> String {
+    let len = value.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&value[..len]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let packed = pack_bytes32("MyToken").unwrap();
+        assert_eq!(&packed[..7], b"MyToken");
+        assert!(packed[7..].iter().all(|&b| b == 0));
+        assert_eq!(unpack_bytes32(&packed), "MyToken");
+    }
+
+    #[test]
+    fn test_limits() {
+        let exact = "x".repeat(32);
+        assert_eq!(unpack_bytes32(&pack_bytes32(&exact).unwrap()), exact);
+        assert!(pack_bytes32(&"x".repeat(33)).is_err());
+        assert_eq!(unpack_bytes32(&[0u8; 32]), "");
+    }
+}
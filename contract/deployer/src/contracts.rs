@@ -0,0 +1,68 @@
+//! The contracts this tool deploys and how to build them
+
+use std::path::{Path, PathBuf};
+
+/// A deployable contract of this repository
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contract {
+    /// The ERC721 implementation every collection clone delegates to
+    Erc721Template,
+    NftFactory,
+    /// The standalone ERC20 every migrating token is cloned from
+    Erc20Template,
+    TokenFactory,
+}
+
+impl Contract {
+    /// Key of this contract in the deployments manifest
+    pub fn key(self) -> &'static str {
+        match self {
+            Contract::Erc721Template => "erc721_template",
+            Contract::NftFactory => "nft_factory",
+            Contract::Erc20Template => "erc20_template",
+            Contract::TokenFactory => "token_factory",
+        }
+    }
+
+    /// Crate directory relative to the `contract/` directory
+    pub fn crate_dir(self) -> &'static str {
+        match self {
+            Contract::Erc721Template => "nft_factory/erc721",
+            Contract::NftFactory => "nft_factory/factory",
+            Contract::Erc20Template => "standalone_erc20",
+            Contract::TokenFactory => "token_factory",
+        }
+    }
+
+    /// Directory whose `target/` the crate builds into (its workspace root)
+    fn target_root(self) -> &'static str {
+        match self {
+            Contract::Erc721Template | Contract::NftFactory => "nft_factory",
+            Contract::Erc20Template => "standalone_erc20",
+            Contract::TokenFactory => "token_factory",
+        }
+    }
+
+    /// Name of the produced `.wasm` (the crate's lib name)
+    pub fn artifact(self) -> &'static str {
+        match self {
+            Contract::Erc721Template => "erc721_collection",
+            Contract::NftFactory => "nft_factory",
+            Contract::Erc20Template => "standalone_erc20",
+            Contract::TokenFactory => "erc20_token_factory",
+        }
+    }
+
+    /// Path of the release wasm once built
+    pub fn wasm_path(self, contracts_dir: &Path) -> PathBuf {
+        contracts_dir
+            .join(self.target_root())
+            .join("target/wasm32-unknown-unknown/release")
+            .join(format!("{}.wasm", self.artifact()))
+    }
+}
+
+/// The `contract/` directory of this repository
+pub fn default_contracts_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
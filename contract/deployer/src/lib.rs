@@ -0,0 +1,15 @@
+//!
+//! Deployment tooling for the Stylus factories
+//!
+//! Builds each contract from its own crate, deploys and activates it, runs
+//! its constructor and records the result in a `deployments.json` manifest
+//! keyed by chain id.
+//!
+
+pub mod contracts;
+pub mod manifest;
+pub mod ops;
+pub mod stylus;
+
+pub use contracts::Contract;
+pub use manifest::{Deployment, Manifest};
@@ -0,0 +1,234 @@
+//! Deploys the Stylus factories and records them in `deployments.json`.
+//!
+//! Usage:
+//!   factory-deploy [--rpc-url <URL>] [--private-key-path <PATH>] [--manifest <PATH>] <COMMAND>
+//!
+//! Commands:
+//!   deploy-erc721          build, deploy and activate the ERC721 collection template
+//!   deploy-nft-factory     deploy the NftFactory, constructed with the template
+//!   deploy-erc20-template  build, deploy and activate the standalone ERC20 template
+//!   deploy-token-factory   deploy the TokenFactory, constructed with the deployer as owner
+//!   deploy-all             all of the above, in order
+//!   create-token           create a token through the recorded TokenFactory
+//!   create-collection      create a collection through the recorded NftFactory
+//!   show                   print the deployments recorded for the chain
+//!
+//! The signing key is read from the file at PRIV_KEY_PATH or taken from PRIVATE_KEY;
+//! every option can also be given through the environment (or a `.env` file).
+
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::{Args, Parser, Subcommand};
+use dotenv::dotenv;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use eyre::{eyre, WrapErr};
+use factory_deployer::{contracts, ops::Deployer, stylus, Contract, Manifest};
+
+#[derive(Parser)]
+#[command(name = "factory-deploy", about = "Deploys and drives the Stylus factories")]
+struct Cli {
+    /// Stylus RPC endpoint
+    #[arg(long, env = "RPC_URL", default_value = "http://localhost:8547", global = true)]
+    rpc_url: String,
+
+    /// File holding the deployer's private key
+    #[arg(long, env = "PRIV_KEY_PATH", global = true)]
+    private_key_path: Option<PathBuf>,
+
+    /// Deployments manifest, keyed by chain id
+    #[arg(long, env = "DEPLOYMENTS_PATH", default_value = "deployments.json", global = true)]
+    manifest: PathBuf,
+
+    /// The repository's `contract/` directory the contracts are built from
+    #[arg(long, env = "CONTRACTS_DIR", global = true)]
+    contracts_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build, deploy and activate the ERC721 collection template
+    DeployErc721(Build),
+    /// Deploy the NftFactory, constructed with the template
+    DeployNftFactory {
+        #[command(flatten)]
+        build: Build,
+        /// ERC721 template to clone, defaults to the recorded one
+        #[arg(long)]
+        template: Option<Address>,
+        #[arg(long, env = "TRUSTED_FORWARDER")]
+        trusted_forwarder: Option<Address>,
+    },
+    /// Build, deploy and activate the standalone ERC20 template
+    DeployErc20Template(Build),
+    /// Deploy the TokenFactory, constructed with the deployer as owner
+    DeployTokenFactory {
+        #[command(flatten)]
+        build: Build,
+        #[arg(long, env = "TRUSTED_FORWARDER")]
+        trusted_forwarder: Option<Address>,
+        /// Standalone ERC20 template for token migration, defaults to the recorded one
+        #[arg(long)]
+        erc20_template: Option<Address>,
+    },
+    /// Deploy both templates and both factories, in order
+    DeployAll {
+        #[arg(long, env = "TRUSTED_FORWARDER")]
+        trusted_forwarder: Option<Address>,
+    },
+    /// Create a token through the recorded TokenFactory
+    CreateToken {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long, default_value_t = 18)]
+        decimals: u8,
+        /// Initial supply in whole tokens
+        #[arg(long)]
+        supply: u64,
+    },
+    /// Create a collection through the recorded NftFactory
+    CreateCollection {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        base_uri: String,
+    },
+    /// Print the deployments recorded for the chain
+    Show,
+}
+
+#[derive(Args)]
+struct Build {
+    /// Deploy this wasm instead of building the contract
+    #[arg(long)]
+    wasm: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    dotenv().ok();
+    let cli = Cli::parse();
+    let contracts_dir = cli.contracts_dir.clone().unwrap_or_else(contracts::default_contracts_dir);
+    let wasm = |contract: Contract, build: &Build| match &build.wasm {
+        Some(path) => std::fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display())),
+        None => stylus::build_wasm(contract, &contracts_dir),
+    };
+
+    let provider = Provider::<Http>::try_from(cli.rpc_url.as_str())?;
+    let manifest = Manifest::load(&cli.manifest)?;
+
+    if let Command::Show = cli.command {
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let deployments = manifest.chain(chain_id).cloned().unwrap_or_default();
+        println!("{}", serde_json::to_string_pretty(&deployments)?);
+        return Ok(());
+    }
+
+    let wallet = LocalWallet::from_str(&private_key(&cli)?)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet.with_chain_id(chain_id)));
+    let mut deployer = Deployer::new(client, manifest).await?;
+
+    match cli.command {
+        Command::DeployErc721(build) => {
+            let deployment = deployer.deploy_erc721_template(&wasm(Contract::Erc721Template, &build)?).await?;
+            println!("ERC721 template deployed at {:?}", deployment.address);
+        }
+        Command::DeployNftFactory { build, template, trusted_forwarder } => {
+            let wasm = wasm(Contract::NftFactory, &build)?;
+            let deployment = deployer.deploy_nft_factory(&wasm, template, trusted_forwarder).await?;
+            println!("NftFactory deployed at {:?}", deployment.address);
+        }
+        Command::DeployErc20Template(build) => {
+            let deployment = deployer.deploy_erc20_template(&wasm(Contract::Erc20Template, &build)?).await?;
+            println!("ERC20 template deployed at {:?}", deployment.address);
+        }
+        Command::DeployTokenFactory { build, trusted_forwarder, erc20_template } => {
+            let wasm = wasm(Contract::TokenFactory, &build)?;
+            let deployment = deployer.deploy_token_factory(&wasm, trusted_forwarder, erc20_template).await?;
+            println!("TokenFactory deployed at {:?}", deployment.address);
+        }
+        Command::DeployAll { trusted_forwarder } => {
+            let build = Build { wasm: None };
+            let template = deployer.deploy_erc721_template(&wasm(Contract::Erc721Template, &build)?).await?;
+            println!("ERC721 template deployed at {:?}", template.address);
+            deployer.manifest.save(&cli.manifest)?;
+
+            let wasm_nft = wasm(Contract::NftFactory, &build)?;
+            let nft_factory = deployer
+                .deploy_nft_factory(&wasm_nft, Some(template.address), trusted_forwarder)
+                .await?;
+            println!("NftFactory deployed at {:?}", nft_factory.address);
+            deployer.manifest.save(&cli.manifest)?;
+
+            let erc20_template = deployer.deploy_erc20_template(&wasm(Contract::Erc20Template, &build)?).await?;
+            println!("ERC20 template deployed at {:?}", erc20_template.address);
+            deployer.manifest.save(&cli.manifest)?;
+
+            let wasm_token = wasm(Contract::TokenFactory, &build)?;
+            let token_factory = deployer
+                .deploy_token_factory(&wasm_token, trusted_forwarder, Some(erc20_template.address))
+                .await?;
+            println!("TokenFactory deployed at {:?}", token_factory.address);
+        }
+        Command::CreateToken { name, symbol, decimals, supply } => {
+            let initial_supply = U256::from(supply) * U256::exp10(decimals.into());
+            let token_id = deployer
+                .create_token(&name, &symbol, decimals, initial_supply, random_salt())
+                .await?;
+            println!("Created token {token_id} ({symbol})");
+        }
+        Command::CreateCollection { name, symbol, base_uri } => {
+            let collection = deployer.create_collection(&name, &symbol, &base_uri).await?;
+            println!("Created collection {symbol} at {collection:?}");
+        }
+        Command::Show => unreachable!(),
+    }
+
+    deployer.manifest.save(&cli.manifest)?;
+    Ok(())
+}
+
+/// The deployer's key, from PRIV_KEY_PATH or PRIVATE_KEY
+fn private_key(cli: &Cli) -> eyre::Result<String> {
+    if let Some(path) = &cli.private_key_path {
+        return read_secret_from_file(path);
+    }
+    std::env::var("PRIVATE_KEY").map_err(|_| eyre!("set --private-key-path, PRIV_KEY_PATH or PRIVATE_KEY"))
+}
+
+fn read_secret_from_file(path: &PathBuf) -> eyre::Result<String> {
+    let f = std::fs::File::open(path).wrap_err_with(|| format!("failed to open {}", path.display()))?;
+    let mut buf_reader = BufReader::new(f);
+    let mut secret = String::new();
+    buf_reader.read_line(&mut secret)?;
+    Ok(secret.trim().to_string())
+}
+
+/// A salt unique to this invocation, so repeated runs never collide
+fn random_salt() -> [u8; 32] {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut salt = [0u8; 32];
+    salt[..16].copy_from_slice(&nanos.to_be_bytes());
+    salt[16..20].copy_from_slice(&std::process::id().to_be_bytes());
+    salt
+}
@@ -0,0 +1,110 @@
+//! The `deployments.json` manifest
+//!
+//! ```json
+//! {
+//!   "412346": {
+//!     "erc721_template": { "address": "0x…", "transaction_hash": "0x…", "block_number": 12 },
+//!     "nft_factory": { … },
+//!     "token_factory": { … }
+//!   }
+//! }
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use ethers::types::{Address, H256};
+use eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::contracts::Contract;
+
+/// Where and when a contract was deployed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: Address,
+    pub transaction_hash: H256,
+    pub block_number: u64,
+}
+
+/// Deployments of every chain, keyed by chain id and then contract key
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Manifest {
+    chains: BTreeMap<String, BTreeMap<String, Deployment>>,
+}
+
+impl Manifest {
+    /// Loads the manifest at `path`, or an empty one if it does not exist yet
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&json).wrap_err_with(|| format!("invalid manifest {}", path.display()))
+    }
+
+    /// Writes the manifest to `path`
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n").wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, chain_id: u64, contract: Contract) -> Option<&Deployment> {
+        self.chains.get(&chain_id.to_string())?.get(contract.key())
+    }
+
+    pub fn set(&mut self, chain_id: u64, contract: Contract, deployment: Deployment) {
+        self.chains
+            .entry(chain_id.to_string())
+            .or_default()
+            .insert(contract.key().to_string(), deployment);
+    }
+
+    /// Returns every deployment on a chain
+    pub fn chain(&self, chain_id: u64) -> Option<&BTreeMap<String, Deployment>> {
+        self.chains.get(&chain_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(byte: u8) -> Deployment {
+        Deployment {
+            address: Address::repeat_byte(byte),
+            transaction_hash: H256::repeat_byte(byte),
+            block_number: byte as u64,
+        }
+    }
+
+    #[test]
+    fn test_chains_are_separate() {
+        let mut manifest = Manifest::default();
+        manifest.set(412346, Contract::TokenFactory, deployment(1));
+        manifest.set(421614, Contract::TokenFactory, deployment(2));
+        manifest.set(412346, Contract::TokenFactory, deployment(3));
+
+        assert_eq!(manifest.get(412346, Contract::TokenFactory), Some(&deployment(3)));
+        assert_eq!(manifest.get(421614, Contract::TokenFactory), Some(&deployment(2)));
+        assert_eq!(manifest.get(421614, Contract::NftFactory), None);
+        assert_eq!(manifest.get(1, Contract::TokenFactory), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("deployments-{}.json", std::process::id()));
+        let mut manifest = Manifest::load(&path).unwrap();
+        assert_eq!(manifest, Manifest::default());
+
+        manifest.set(412346, Contract::Erc721Template, deployment(4));
+        manifest.set(412346, Contract::NftFactory, deployment(5));
+        manifest.save(&path).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["412346"]["nft_factory"]["block_number"], 5);
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Deployment and setup steps, each recorded in the manifest as it completes
+
+use std::sync::Arc;
+
+use ethers::{
+    abi::Token,
+    providers::Middleware,
+    types::{Address, TransactionReceipt, U256},
+};
+use eyre::{bail, eyre, WrapErr};
+use factory_client::{events, names, NftFactory, TokenFactory};
+
+use crate::{
+    contracts::Contract,
+    manifest::{Deployment, Manifest},
+    stylus,
+};
+
+/// A deployment session on one chain
+pub struct Deployer<M> {
+    pub client: Arc<M>,
+    pub chain_id: u64,
+    pub manifest: Manifest,
+}
+
+impl<M: Middleware + 'static> Deployer<M> {
+    pub async fn new(client: Arc<M>, manifest: Manifest) -> eyre::Result<Self> {
+        let chain_id = client
+            .get_chainid()
+            .await
+            .map_err(|err| eyre!("failed to fetch chain id: {err}"))?
+            .as_u64();
+        Ok(Self { client, chain_id, manifest })
+    }
+
+    /// Address of a contract already recorded for this chain
+    pub fn address_of(&self, contract: Contract) -> eyre::Result<Address> {
+        self.manifest
+            .get(self.chain_id, contract)
+            .map(|deployment| deployment.address)
+            .ok_or_else(|| eyre!("no {} recorded for chain {}", contract.key(), self.chain_id))
+    }
+
+    /// Deploys and activates a contract's wasm, recording it in the manifest
+    pub async fn deploy(&mut self, contract: Contract, wasm: &[u8]) -> eyre::Result<Deployment> {
+        let deployment = stylus::deploy_program(self.client.clone(), wasm)
+            .await
+            .wrap_err_with(|| format!("failed to deploy {}", contract.key()))?;
+        self.manifest.set(self.chain_id, contract, deployment.clone());
+        Ok(deployment)
+    }
+
+    /// Deploys a contract whose `#[constructor]` takes `args`, recording it in the manifest
+    pub async fn deploy_with_constructor(
+        &mut self,
+        contract: Contract,
+        wasm: &[u8],
+        args: &[Token],
+    ) -> eyre::Result<Deployment> {
+        let init_data = stylus::constructor_calldata(args);
+        let deployment = stylus::deploy_program_with_constructor(self.client.clone(), wasm, init_data)
+            .await
+            .wrap_err_with(|| format!("failed to deploy {}", contract.key()))?;
+        self.manifest.set(self.chain_id, contract, deployment.clone());
+        Ok(deployment)
+    }
+
+    /// Account deployments are sent from, which ends up owning the factories
+    fn sender(&self) -> eyre::Result<Address> {
+        self.client
+            .default_sender()
+            .ok_or_else(|| eyre!("the client has no default sender"))
+    }
+
+    /// Deploys the ERC721 template collections are cloned from
+    pub async fn deploy_erc721_template(&mut self, wasm: &[u8]) -> eyre::Result<Deployment> {
+        self.deploy(Contract::Erc721Template, wasm).await
+    }
+
+    /// Deploys the NftFactory, constructed with a template and the sender as owner
+    ///
+    /// Without an explicit `template` the one recorded for this chain is used.
+    pub async fn deploy_nft_factory(
+        &mut self,
+        wasm: &[u8],
+        template: Option<Address>,
+        trusted_forwarder: Option<Address>,
+    ) -> eyre::Result<Deployment> {
+        let template = match template {
+            Some(template) => template,
+            None => self.address_of(Contract::Erc721Template)?,
+        };
+
+        let args = [Token::Address(self.sender()?), Token::Address(template)];
+        let deployment = self.deploy_with_constructor(Contract::NftFactory, wasm, &args).await?;
+        let factory = NftFactory::new(deployment.address, self.client.clone());
+        if let Some(forwarder) = trusted_forwarder {
+            confirm(factory.set_trusted_forwarder(forwarder).send().await?.await?, "setTrustedForwarder")?;
+        }
+        Ok(deployment)
+    }
+
+    /// Deploys the standalone ERC20 migrating tokens are cloned from
+    pub async fn deploy_erc20_template(&mut self, wasm: &[u8]) -> eyre::Result<Deployment> {
+        self.deploy(Contract::Erc20Template, wasm).await
+    }
+
+    /// Deploys the TokenFactory, constructed with the sender as owner
+    ///
+    /// Without an explicit `erc20_template` the one recorded for this chain is
+    /// used; with neither, token migration stays disabled.
+    pub async fn deploy_token_factory(
+        &mut self,
+        wasm: &[u8],
+        trusted_forwarder: Option<Address>,
+        erc20_template: Option<Address>,
+    ) -> eyre::Result<Deployment> {
+        let erc20_template = erc20_template.or_else(|| self.address_of(Contract::Erc20Template).ok());
+
+        let args = [Token::Address(self.sender()?)];
+        let deployment = self.deploy_with_constructor(Contract::TokenFactory, wasm, &args).await?;
+        let factory = TokenFactory::new(deployment.address, self.client.clone());
+        if let Some(forwarder) = trusted_forwarder {
+            confirm(factory.set_trusted_forwarder(forwarder).send().await?.await?, "setTrustedForwarder")?;
+        }
+        if let Some(template) = erc20_template {
+            confirm(factory.set_erc_20_template(template).send().await?.await?, "setErc20Template")?;
+        }
+        Ok(deployment)
+    }
+
+    /// Creates a token through the recorded TokenFactory, returning its id
+    pub async fn create_token(
+        &self,
+        name: &str,
+        symbol: &str,
+        decimals: u8,
+        initial_supply: U256,
+        salt: [u8; 32],
+    ) -> eyre::Result<U256> {
+        let address = self.address_of(Contract::TokenFactory)?;
+        let factory = TokenFactory::new(address, self.client.clone());
+        let call = factory.create_token_with_salt(
+            names::pack_bytes32(name)?,
+            names::pack_bytes32(symbol)?,
+            U256::from(decimals),
+            initial_supply,
+            salt,
+            0, // A standard (non-rebasing) token
+            0, // With no optional features
+        );
+        let creator = call.tx.from().copied().or_else(|| self.client.default_sender());
+        confirm(call.send().await?.await?, "createTokenWithSalt")?;
+
+        let creator = creator.ok_or_else(|| eyre!("no sender to resolve the token id for"))?;
+        Ok(factory.token_id_for(creator, salt).call().await?)
+    }
+
+    /// Creates a collection through the recorded NftFactory, returning its address
+    pub async fn create_collection(&self, name: &str, symbol: &str, base_uri: &str) -> eyre::Result<Address> {
+        let address = self.address_of(Contract::NftFactory)?;
+        let factory = NftFactory::new(address, self.client.clone());
+        let receipt = factory
+            .create_collection(name.into(), symbol.into(), base_uri.into())
+            .send()
+            .await?
+            .await?;
+        let receipt = confirm(receipt, "createCollection")?;
+        events::created_collection(&receipt, address)
+            .map(|created| created.collection_address)
+            .ok_or_else(|| eyre!("no CollectionCreated event in {:?}", receipt.transaction_hash))
+    }
+}
+
+/// Checks a transaction made it into a block without reverting
+fn confirm(receipt: Option<TransactionReceipt>, what: &str) -> eyre::Result<TransactionReceipt> {
+    let receipt = receipt.ok_or_else(|| eyre!("{what} transaction was dropped"))?;
+    if receipt.status != Some(1u64.into()) {
+        bail!("{what} reverted in {:?}", receipt.transaction_hash);
+    }
+    Ok(receipt)
+}
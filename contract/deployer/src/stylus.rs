@@ -0,0 +1,279 @@
+//! Building, deploying and activating Stylus programs
+//!
+//! Mirrors what `cargo stylus deploy` does: the wasm is brotli-compressed,
+//! prefixed with the Stylus discriminant, wrapped in init code that returns it,
+//! deployed with a plain CREATE and finally activated through ArbWasm.
+//!
+//! Programs with a `#[constructor]` go through the StylusDeployer instead,
+//! which deploys, activates and runs the constructor in one transaction, so
+//! nobody can call the constructor of a fresh deployment before its deployer.
+
+use std::{io::Write, path::Path, process::Command, sync::Arc};
+
+use ethers::{
+    abi::{self, Token},
+    contract::{abigen, EthLogDecode},
+    providers::{spoof, Middleware, RawCall},
+    types::{Address, TransactionRequest, H160, U256},
+    utils::{id, keccak256, parse_ether},
+};
+use eyre::{bail, eyre, WrapErr};
+
+use crate::{contracts::Contract, manifest::Deployment};
+
+abigen!(
+    ArbWasm,
+    r#"[
+        function activateProgram(address program) external payable returns (uint16 version, uint256 dataFee)
+        function codehashVersion(bytes32 codehash) external view returns (uint16 version)
+    ]"#
+);
+
+abigen!(
+    StylusDeployer,
+    r#"[
+        function deploy(bytes bytecode, bytes initData, uint256 initValue, bytes32 salt) external payable returns (address)
+        event ContractDeployed(address deployedContract)
+    ]"#
+);
+
+/// The ArbWasm precompile (0x…71)
+pub const ARB_WASM: Address = H160([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x71,
+]);
+
+/// The StylusDeployer, at the same address on every Arbitrum chain and the Nitro dev node
+pub const STYLUS_DEPLOYER: Address = H160([
+    0xce, 0xcb, 0xa2, 0xf1, 0xdc, 0x23, 0x4f, 0x70, 0xdd, 0x89, 0xf2, 0x04, 0x10, 0x29, 0x80, 0x7f, 0x8d, 0x03, 0xa9, 0x90,
+]);
+
+/// Where activation is simulated to learn its fee, no code lives there
+const SCRATCH_PROGRAM: Address = H160([0x57; 20]);
+
+/// Prefix marking deployed code as a Stylus program (EOF-style magic, version 0, no dictionary)
+const STYLUS_DISCRIMINANT: [u8; 4] = [0xef, 0xf0, 0x00, 0x00];
+
+/// Extra data fee sent on activation, in percent, in case prices move
+const DATA_FEE_BUMP_PERCENT: u64 = 20;
+
+/// Builds a contract's release wasm
+pub fn build_wasm(contract: Contract, contracts_dir: &Path) -> eyre::Result<Vec<u8>> {
+    let crate_dir = contracts_dir.join(contract.crate_dir());
+
+    // Run from the crate so its .cargo/config.toml wasm flags apply
+    let status = Command::new("cargo")
+        .current_dir(&crate_dir)
+        .args(["build", "--release", "--lib", "--target", "wasm32-unknown-unknown"])
+        .status()
+        .wrap_err("failed to run cargo")?;
+    if !status.success() {
+        bail!("building {} failed", contract.key());
+    }
+
+    let path = contract.wasm_path(contracts_dir);
+    std::fs::read(&path).wrap_err_with(|| format!("failed to read {}", path.display()))
+}
+
+/// Compresses a wasm into the code a Stylus program is deployed with
+pub fn program_code(wasm: &[u8]) -> eyre::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        writer.write_all(wasm)?;
+    }
+
+    let mut code = STYLUS_DISCRIMINANT.to_vec();
+    code.extend(compressed);
+    Ok(code)
+}
+
+/// Wraps program code in init code that returns it
+pub fn init_code(code: &[u8]) -> Vec<u8> {
+    // PUSH32 len, DUP1, PUSH1 43, PUSH1 0, CODECOPY, PUSH1 0, RETURN, then a version byte
+    const PRELUDE_LEN: u8 = 42;
+
+    let mut init = Vec::with_capacity(PRELUDE_LEN as usize + 1 + code.len());
+    init.push(0x7f);
+    init.extend_from_slice(&{
+        let mut len = [0u8; 32];
+        U256::from(code.len()).to_big_endian(&mut len);
+        len
+    });
+    init.extend_from_slice(&[0x80, 0x60, PRELUDE_LEN + 1, 0x60, 0x00, 0x39, 0x60, 0x00, 0xf3]);
+    init.push(0x00);
+    init.extend_from_slice(code);
+    init
+}
+
+/// Calldata running a program's `#[constructor]` with `args`
+pub fn constructor_calldata(args: &[Token]) -> Vec<u8> {
+    let mut data = id("stylus_constructor()").to_vec();
+    data.extend(abi::encode(args));
+    data
+}
+
+/// Deploys and activates a wasm, returning where it landed
+pub async fn deploy_program<M: Middleware + 'static>(client: Arc<M>, wasm: &[u8]) -> eyre::Result<Deployment> {
+    let code = program_code(wasm)?;
+    let tx = TransactionRequest::new().data(init_code(&code));
+    let receipt = client
+        .send_transaction(tx, None)
+        .await
+        .map_err(|err| eyre!("deployment failed: {err}"))?
+        .await?
+        .ok_or_else(|| eyre!("deployment transaction was dropped"))?;
+    if receipt.status != Some(1u64.into()) {
+        bail!("deployment transaction {:?} reverted", receipt.transaction_hash);
+    }
+
+    let address = receipt
+        .contract_address
+        .ok_or_else(|| eyre!("deployment receipt has no contract address"))?;
+    activate_program(client, address, &code).await?;
+
+    Ok(Deployment {
+        address,
+        transaction_hash: receipt.transaction_hash,
+        block_number: receipt.block_number.map(|number| number.as_u64()).unwrap_or_default(),
+    })
+}
+
+/// Deploys a wasm through the StylusDeployer, running its constructor with `init_data`
+pub async fn deploy_program_with_constructor<M: Middleware + 'static>(
+    client: Arc<M>,
+    wasm: &[u8],
+    init_data: Vec<u8>,
+) -> eyre::Result<Deployment> {
+    let code = program_code(wasm)?;
+    let data_fee = activation_fee(client.clone(), &code).await?;
+    let value = data_fee * (100 + DATA_FEE_BUMP_PERCENT) / 100;
+
+    // The StylusDeployer refunds whatever activation did not use
+    let deployer = StylusDeployer::new(STYLUS_DEPLOYER, client);
+    let receipt = deployer
+        .deploy(init_code(&code).into(), init_data.into(), U256::zero(), [0; 32])
+        .value(value)
+        .send()
+        .await
+        .map_err(|err| eyre!("deployment failed: {err}"))?
+        .await?
+        .ok_or_else(|| eyre!("deployment transaction was dropped"))?;
+    if receipt.status != Some(1u64.into()) {
+        bail!("deployment transaction {:?} reverted", receipt.transaction_hash);
+    }
+
+    let address = receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == STYLUS_DEPLOYER)
+        .find_map(|log| ContractDeployedFilter::decode_log(&log.clone().into()).ok())
+        .map(|event| event.deployed_contract)
+        .ok_or_else(|| eyre!("deployment receipt has no ContractDeployed event"))?;
+
+    Ok(Deployment {
+        address,
+        transaction_hash: receipt.transaction_hash,
+        block_number: receipt.block_number.map(|number| number.as_u64()).unwrap_or_default(),
+    })
+}
+
+/// Data fee activating `code` costs, zero if identical code is already active
+///
+/// Activation is simulated against the code placed at a scratch address, as
+/// the program itself does not exist yet.
+pub async fn activation_fee<M: Middleware + 'static>(client: Arc<M>, code: &[u8]) -> eyre::Result<U256> {
+    let arb_wasm = ArbWasm::new(ARB_WASM, client);
+    if arb_wasm.codehash_version(keccak256(code)).call().await.is_ok() {
+        return Ok(U256::zero());
+    }
+
+    let state = spoof::code(SCRATCH_PROGRAM, code.to_vec().into());
+    let call = arb_wasm.activate_program(SCRATCH_PROGRAM).value(parse_ether("0.1")?);
+    let (_, data_fee) = call
+        .call_raw()
+        .state(&state)
+        .await
+        .wrap_err("activation simulation failed")?;
+    Ok(data_fee)
+}
+
+/// Activates a deployed program, paying its data fee
+///
+/// Programs whose code is already active (identical wasm deployed before) are left as is.
+pub async fn activate_program<M: Middleware + 'static>(client: Arc<M>, program: Address, code: &[u8]) -> eyre::Result<()> {
+    let arb_wasm = ArbWasm::new(ARB_WASM, client);
+
+    if arb_wasm.codehash_version(keccak256(code)).call().await.is_ok() {
+        return Ok(());
+    }
+
+    // Simulate with a generous value to learn the fee, any excess would be refunded anyway
+    let (_, data_fee) = arb_wasm
+        .activate_program(program)
+        .value(parse_ether("0.1")?)
+        .call()
+        .await
+        .wrap_err("activation simulation failed")?;
+    let value = data_fee * (100 + DATA_FEE_BUMP_PERCENT) / 100;
+
+    let receipt = arb_wasm
+        .activate_program(program)
+        .value(value)
+        .send()
+        .await
+        .wrap_err("activation failed")?
+        .await?
+        .ok_or_else(|| eyre!("activation transaction was dropped"))?;
+    if receipt.status != Some(1u64.into()) {
+        bail!("activation of {program:?} reverted");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_program_code_roundtrip() {
+        let wasm = b"\0asm\x01\0\0\0 pretend this is a contract".repeat(8);
+        let code = program_code(&wasm).unwrap();
+        assert_eq!(&code[..4], &STYLUS_DISCRIMINANT);
+
+        let mut decompressed = Vec::new();
+        brotli::Decompressor::new(&code[4..], 4096).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, wasm);
+    }
+
+    #[test]
+    fn test_init_code_layout() {
+        let code = vec![0xab; 300];
+        let init = init_code(&code);
+
+        assert_eq!(init[0], 0x7f);
+        assert_eq!(U256::from_big_endian(&init[1..33]), U256::from(300));
+        // CODECOPY reads from right after the prelude and version byte
+        assert_eq!(init[35], 43);
+        assert_eq!(init[42], 0x00);
+        assert_eq!(&init[43..], &code[..]);
+    }
+
+    #[test]
+    fn test_constructor_calldata() {
+        let owner = Address::repeat_byte(0xab);
+        let data = constructor_calldata(&[Token::Address(owner)]);
+
+        // The selector stylus-sdk routes to `#[constructor]`
+        assert_eq!(&data[..4], &keccak256("stylus_constructor()")[..4]);
+        assert_eq!(&data[4..], abi::encode(&[Token::Address(owner)]).as_slice());
+    }
+
+    #[test]
+    fn test_arb_wasm_precompile() {
+        assert_eq!(
+            ARB_WASM,
+            "0x0000000000000000000000000000000000000071".parse::<Address>().unwrap()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Deploys everything to a local Nitro dev node and exercises both factories
+//!
+//! Needs built wasms and a running dev node, so it is ignored by default:
+//!   DEV_NODE_PRIVATE_KEY=0x… cargo test --test dev_node -- --ignored
+//! DEV_NODE_RPC_URL defaults to http://localhost:8547.
+
+use std::sync::Arc;
+
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::U256,
+};
+use factory_client::{token_info, Erc721, NftFactory, TokenFactory};
+use factory_deployer::{contracts, ops::Deployer, stylus, Contract, Manifest};
+
+#[tokio::test]
+#[ignore = "needs a local dev node"]
+async fn deploys_and_drives_factories() -> eyre::Result<()> {
+    let rpc_url = std::env::var("DEV_NODE_RPC_URL").unwrap_or_else(|_| "http://localhost:8547".into());
+    let key = std::env::var("DEV_NODE_PRIVATE_KEY").expect("DEV_NODE_PRIVATE_KEY is not set");
+
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = key.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let me = wallet.address();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let contracts_dir = contracts::default_contracts_dir();
+    let mut deployer = Deployer::new(client.clone(), Manifest::default()).await?;
+    deployer
+        .deploy_erc721_template(&stylus::build_wasm(Contract::Erc721Template, &contracts_dir)?)
+        .await?;
+    let nft_factory = deployer
+        .deploy_nft_factory(&stylus::build_wasm(Contract::NftFactory, &contracts_dir)?, None, None)
+        .await?;
+    let erc20_template = deployer
+        .deploy_erc20_template(&stylus::build_wasm(Contract::Erc20Template, &contracts_dir)?)
+        .await?;
+    let token_factory = deployer
+        .deploy_token_factory(&stylus::build_wasm(Contract::TokenFactory, &contracts_dir)?, None, None)
+        .await?;
+
+    let manifest = &deployer.manifest;
+    assert_eq!(manifest.chain(chain_id).map(|chain| chain.len()), Some(4));
+    assert_eq!(manifest.get(chain_id, Contract::TokenFactory), Some(&token_factory));
+
+    let nft = NftFactory::new(nft_factory.address, client.clone());
+    assert_eq!(nft.get_implementation().call().await?, deployer.address_of(Contract::Erc721Template)?);
+    let tokens = TokenFactory::new(token_factory.address, client.clone());
+    assert_eq!(tokens.owner().call().await?, me);
+    assert_eq!(tokens.erc_20_template().call().await?, erc20_template.address);
+
+    let token_id = deployer
+        .create_token("Dev Token", "DEV", 6, U256::from(1_000_000), [7; 32])
+        .await?;
+    let info = token_info(&tokens, token_id).await?;
+    assert_eq!((info.name.as_str(), info.symbol.as_str(), info.creator), ("Dev Token", "DEV", me));
+
+    let collection = deployer.create_collection("Dev Art", "DART", "ipfs://dev/").await?;
+    let collection = Erc721::new(collection, client);
+    assert_eq!(collection.symbol().call().await?, "DART");
+    assert_eq!(collection.creator().call().await?, me);
+
+    Ok(())
+}
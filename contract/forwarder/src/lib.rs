@@ -0,0 +1,320 @@
+//!
+//! Stylus ERC-2771 Minimal Forwarder
+//!
+//! Relays EIP-712 signed requests to contracts that trust this forwarder
+//! (TokenFactory, NftFactory and the ERC721 collections it deploys).
+//! The signer's address is appended to the forwarded calldata so the target
+//! can recover it with its `_msg_sender()` helper.
+//!
+//! Signed type:
+//! ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,bytes data)
+//!
+//! The program is ABI-equivalent with Solidity.
+//! To export the ABI, run `cargo stylus export-abi`.
+//!
+//! Note: this code is a template and has not been audited.
+//!
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use stylus_common::eip712::{self, Nonces};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    alloy_sol_types::{sol, SolError},
+    crypto::keccak,
+    prelude::*,
+    stylus_core::calls::{CallContext, MutatingCallContext},
+};
+
+pub use stylus_common::eip712::InvalidNonce;
+
+/// Type string of the signed request
+const FORWARD_REQUEST_TYPE: &[u8] =
+    b"ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,bytes data)";
+
+const DOMAIN_NAME: &[u8] = b"MinimalForwarder";
+const DOMAIN_VERSION: &[u8] = b"0.0.1";
+
+sol_storage! {
+    #[entrypoint]
+    pub struct MinimalForwarder {
+        Nonces nonces;
+    }
+}
+
+sol! {
+    event RequestExecuted(address indexed from, address indexed to, uint256 nonce, bool success);
+
+    error InvalidSignature(address from);
+    error ValueMismatch(uint256 value, uint256 sent);
+    error InsufficientGas(uint256 gas);
+}
+
+/// Gas and value a request is forwarded with
+struct Forward {
+    gas: u64,
+    value: U256,
+}
+
+impl CallContext for Forward {
+    fn gas(&self) -> u64 {
+        self.gas
+    }
+}
+
+// The forwarder is not reentrant, and the host flushes its storage before calling
+unsafe impl MutatingCallContext for Forward {
+    fn value(&self) -> U256 {
+        self.value
+    }
+}
+
+#[public]
+impl MinimalForwarder {
+    /// Returns the next nonce expected for `from`
+    pub fn get_nonce(&self, from: Address) -> U256 {
+        self.nonces.nonce(from)
+    }
+
+    /// Returns the EIP-712 domain separator of this forwarder
+    pub fn domain_separator(&self) -> B256 {
+        eip712::domain_separator(DOMAIN_NAME, DOMAIN_VERSION, self.vm().chain_id(), self.vm().contract_address())
+    }
+
+    /// Returns true if `signature` is a valid signature by `from` over the request
+    /// and `nonce` is the next nonce for `from`
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify(
+        &self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: Bytes,
+        signature: Bytes,
+    ) -> bool {
+        if self.nonces.nonce(from) != nonce {
+            return false;
+        }
+
+        let digest = self._hash_request(from, to, value, gas, nonce, &data);
+        from != Address::ZERO && eip712::recover(self, digest, &signature) == Some(from)
+    }
+
+    /// Executes a signed request, appending `from` to the calldata (ERC-2771)
+    /// The relayer sends exactly the request's value and enough gas for the request's gas.
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: Bytes,
+        signature: Bytes,
+    ) -> Result<(bool, Bytes), Vec<u8>> {
+        let digest = self._hash_request(from, to, value, gas, nonce, &data);
+        if from == Address::ZERO || eip712::recover(self, digest, &signature) != Some(from) {
+            return Err(InvalidSignature { from }.abi_encode());
+        }
+        let sent = self.vm().msg_value();
+        if sent != value {
+            return Err(ValueMismatch { value, sent }.abi_encode());
+        }
+        self.nonces.use_nonce(from, nonce)?;
+
+        let mut calldata = data.to_vec();
+        calldata.extend_from_slice(from.as_slice());
+
+        let gas_limit = gas.saturating_to::<u64>();
+        let (success, return_data) = match self.vm().call(&Forward { gas: gas_limit, value }, to, &calldata) {
+            Ok(output) => (true, output),
+            Err(error) => (false, Vec::from(error)),
+        };
+        // The call only gets 63/64 of what is left, so a relayer could starve it on
+        // purpose; with less than 1/64 of the signed gas left it may have been
+        if self.vm().evm_gas_left() <= gas_limit / 63 {
+            return Err(InsufficientGas { gas }.abi_encode());
+        }
+
+        log(self.vm(), RequestExecuted { from, to, nonce, success });
+
+        Ok((success, return_data.into()))
+    }
+}
+
+// Internal helper functions
+impl MinimalForwarder {
+    /// Computes the EIP-712 digest of a forward request
+    fn _hash_request(
+        &self,
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: &[u8],
+    ) -> B256 {
+        let mut encoded = Vec::with_capacity(7 * 32);
+        encoded.extend_from_slice(keccak(FORWARD_REQUEST_TYPE).as_slice());
+        encoded.extend_from_slice(from.into_word().as_slice());
+        encoded.extend_from_slice(to.into_word().as_slice());
+        encoded.extend_from_slice(&value.to_be_bytes::<32>());
+        encoded.extend_from_slice(&gas.to_be_bytes::<32>());
+        encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+        encoded.extend_from_slice(keccak(data).as_slice());
+        let struct_hash = keccak(&encoded);
+
+        eip712::typed_data_hash(self.domain_separator(), struct_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::uint;
+    use k256::ecdsa::SigningKey;
+    use stylus_common::eip712::ECRECOVER;
+    use stylus_sdk::testing::*;
+
+    /// A request as the forwarder takes it
+    struct Request {
+        from: Address,
+        to: Address,
+        value: U256,
+        gas: U256,
+        nonce: U256,
+        data: Vec<u8>,
+    }
+
+    impl Request {
+        /// Signs the request with `key`, teaching the test VM's ecrecover the signature
+        fn sign(&self, vm: &TestVM, forwarder: &MinimalForwarder, key: &SigningKey) -> Bytes {
+            let digest = forwarder._hash_request(self.from, self.to, self.value, self.gas, self.nonce, &self.data);
+            let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_slice()).unwrap();
+            let v = 27 + recovery_id.to_byte();
+
+            let mut input = [0u8; 128];
+            input[..32].copy_from_slice(digest.as_slice());
+            input[63] = v;
+            input[64..].copy_from_slice(&signature.to_bytes());
+            vm.mock_static_call(ECRECOVER, input.to_vec(), Ok(Address::from_private_key(key).into_word().to_vec()));
+
+            Bytes([&signature.to_bytes()[..], &[v]].concat())
+        }
+
+        fn verify(&self, forwarder: &MinimalForwarder, signature: &Bytes) -> bool {
+            forwarder.verify(self.from, self.to, self.value, self.gas, self.nonce, Bytes(self.data.clone()), signature.clone())
+        }
+
+        fn execute(&self, forwarder: &mut MinimalForwarder, signature: &Bytes) -> Result<(bool, Bytes), Vec<u8>> {
+            forwarder.execute(self.from, self.to, self.value, self.gas, self.nonce, Bytes(self.data.clone()), signature.clone())
+        }
+    }
+
+    fn signer() -> SigningKey {
+        SigningKey::from_slice(&[0x42u8; 32]).unwrap()
+    }
+
+    fn request(from: Address) -> Request {
+        Request {
+            from,
+            to: Address::from([9u8; 20]),
+            value: U256::ZERO,
+            gas: U256::from(100_000),
+            nonce: U256::ZERO,
+            data: vec![0xa9, 0x05, 0x9c, 0xbb, 1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let vm = TestVM::default();
+        let forwarder = MinimalForwarder::from(&vm);
+        let key = signer();
+        let from = Address::from_private_key(&key);
+
+        let req = request(from);
+        let signature = req.sign(&vm, &forwarder, &key);
+        assert!(req.verify(&forwarder, &signature));
+
+        // The signature covers every field and only holds for the next nonce
+        assert!(!Request { data: vec![0xa9, 0x05, 0x9c, 0xbb, 1, 2, 4], ..request(from) }.verify(&forwarder, &signature));
+        assert!(!Request { from: Address::from([1u8; 20]), ..request(from) }.verify(&forwarder, &signature));
+        let next = Request { nonce: U256::from(1), ..request(from) };
+        let next_signature = next.sign(&vm, &forwarder, &key);
+        assert!(!next.verify(&forwarder, &next_signature));
+
+        // Nor does the same signature in its high-s form, which ecrecover would accept
+        let order = uint!(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141_U256);
+        let s = U256::from_be_slice(&signature[32..64]);
+        let mut malleated = signature.to_vec();
+        malleated[32..64].copy_from_slice(&(order - s).to_be_bytes::<32>());
+        malleated[64] ^= 1;
+        let digest = forwarder._hash_request(req.from, req.to, req.value, req.gas, req.nonce, &req.data);
+        let input = [digest.as_slice(), &[0u8; 31], &malleated[64..], &malleated[..64]].concat();
+        vm.mock_static_call(ECRECOVER, input, Ok(from.into_word().to_vec()));
+        assert!(!req.verify(&forwarder, &Bytes(malleated)));
+    }
+
+    #[test]
+    fn test_execute_appends_sender() {
+        let vm = TestVM::default();
+        let mut forwarder = MinimalForwarder::from(&vm);
+        let key = signer();
+        let from = Address::from_private_key(&key);
+
+        // The target sees the signer appended to the calldata
+        let req = request(from);
+        let relayed = [req.data.as_slice(), from.as_slice()].concat();
+        vm.mock_call(req.to, relayed, Ok(vec![7u8; 32]));
+        let signature = req.sign(&vm, &forwarder, &key);
+        assert_eq!(req.execute(&mut forwarder, &signature), Ok((true, Bytes(vec![7u8; 32]))));
+        assert_eq!(forwarder.get_nonce(from), U256::from(1));
+
+        // A request runs once
+        assert_eq!(
+            req.execute(&mut forwarder, &signature),
+            Err(InvalidNonce { owner: from, expected: U256::from(1), got: U256::ZERO }.abi_encode())
+        );
+        let mut forged = signature.to_vec();
+        forged[0] ^= 1;
+        let next = Request { nonce: U256::from(1), ..request(from) };
+        assert_eq!(next.execute(&mut forwarder, &Bytes(forged)), Err(InvalidSignature { from }.abi_encode()));
+    }
+
+    #[test]
+    fn test_execute_value_and_gas() {
+        let vm = TestVM::default();
+        let mut forwarder = MinimalForwarder::from(&vm);
+        let key = signer();
+        let from = Address::from_private_key(&key);
+
+        // The relayer funds the call with exactly the signed value
+        let req = Request { value: U256::from(500), ..request(from) };
+        let signature = req.sign(&vm, &forwarder, &key);
+        vm.set_value(U256::from(499));
+        assert_eq!(
+            req.execute(&mut forwarder, &signature),
+            Err(ValueMismatch { value: U256::from(500), sent: U256::from(499) }.abi_encode())
+        );
+
+        vm.set_value(U256::from(500));
+        vm.set_gas_left(100_000 / 63 + 1);
+        assert!(req.execute(&mut forwarder, &signature).unwrap().0);
+
+        // And cannot starve it of the signed gas
+        let next = Request { value: U256::from(500), nonce: U256::from(1), ..request(from) };
+        let signature = next.sign(&vm, &forwarder, &key);
+        vm.set_gas_left(100_000 / 63);
+        assert_eq!(next.execute(&mut forwarder, &signature), Err(InsufficientGas { gas: next.gas }.abi_encode()));
+    }
+}
@@ -6,5 +6,5 @@ pub extern "C" fn main() {}
 
 #[cfg(feature = "export-abi")]
 fn main() {
-    erc721_nft_factory::print_from_args();
+    minimal_forwarder::print_from_args();
 }
@@ -0,0 +1,10 @@
+//! Event definitions shared with the contracts
+//!
+//! These modules are the contracts' own `events.rs` files, so the indexer can
+//! never drift from what is actually emitted on-chain.
+
+#[path = "../../token_factory/src/events.rs"]
+pub mod token_factory;
+
+#[path = "../../nft_factory/src/events.rs"]
+pub mod nft_factory;
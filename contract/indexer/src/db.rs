@@ -0,0 +1,612 @@
+//! SQLite registry of tokens, balances, collections and NFT ownership
+
+use std::path::Path;
+
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
+use eyre::{bail, eyre, WrapErr};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::decode::{decode, Emitter, RegistryEvent};
+use crate::log::RawLog;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    hash TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS logs (
+    block_number INTEGER NOT NULL,
+    log_index INTEGER NOT NULL,
+    block_hash TEXT NOT NULL,
+    transaction_hash TEXT NOT NULL,
+    address TEXT NOT NULL,
+    topics TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (block_number, log_index)
+);
+CREATE TABLE IF NOT EXISTS tokens (
+    token_id INTEGER PRIMARY KEY,
+    creator TEXT NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    initial_supply TEXT NOT NULL,
+    total_supply TEXT NOT NULL,
+    created_block INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS token_balances (
+    token_id INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    balance TEXT NOT NULL,
+    PRIMARY KEY (token_id, account)
+);
+CREATE TABLE IF NOT EXISTS token_allowances (
+    token_id INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    spender TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (token_id, owner, spender)
+);
+CREATE TABLE IF NOT EXISTS collections (
+    collection_id INTEGER PRIMARY KEY,
+    address TEXT NOT NULL UNIQUE,
+    creator TEXT NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    base_uri TEXT NOT NULL,
+    created_block INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS nft_owners (
+    collection TEXT NOT NULL,
+    token_id INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    PRIMARY KEY (collection, token_id)
+);
+";
+
+/// Tables derived from the `logs` table, cleared and replayed on reorgs
+const DERIVED_TABLES: [&str; 5] = ["tokens", "token_balances", "token_allowances", "collections", "nft_owners"];
+
+/// Registry id of the TokenFactory's WETH (token id `U256::MAX` on chain)
+pub const WETH_TOKEN_ID: u64 = u64::MAX;
+
+/// A token row of the registry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenRecord {
+    pub token_id: u64,
+    pub creator: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub initial_supply: U256,
+    pub total_supply: U256,
+    pub created_block: u64,
+}
+
+/// A collection row of the registry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectionRecord {
+    pub collection_id: u64,
+    pub address: Address,
+    pub creator: Address,
+    pub name: String,
+    pub symbol: String,
+    pub base_uri: String,
+    pub created_block: u64,
+}
+
+/// The indexed state of one TokenFactory / NftFactory deployment
+pub struct Registry {
+    conn: Connection,
+    token_factory: Address,
+    nft_factory: Address,
+}
+
+impl Registry {
+    /// Opens (or creates) the registry database at `path` for the given factories
+    pub fn open(path: impl AsRef<Path>, token_factory: Address, nft_factory: Address) -> eyre::Result<Self> {
+        let conn = Connection::open(path).wrap_err("failed to open registry database")?;
+        Self::from_connection(conn, token_factory, nft_factory)
+    }
+
+    /// Creates a registry that only lives in memory
+    pub fn in_memory(token_factory: Address, nft_factory: Address) -> eyre::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?, token_factory, nft_factory)
+    }
+
+    fn from_connection(conn: Connection, token_factory: Address, nft_factory: Address) -> eyre::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        let registry = Self { conn, token_factory, nft_factory };
+        registry.bind("token_factory", token_factory)?;
+        registry.bind("nft_factory", nft_factory)?;
+        Ok(registry)
+    }
+
+    /// Records the factory address a database was created for, refusing to mix deployments
+    fn bind(&self, key: &str, address: Address) -> eyre::Result<()> {
+        match self.meta(key)? {
+            Some(stored) if stored != hex_address(address) => {
+                bail!("database was created for {key} {stored}, not {}", hex_address(address))
+            }
+            Some(_) => Ok(()),
+            None => self.set_meta(key, &hex_address(address)),
+        }
+    }
+
+    fn meta(&self, key: &str) -> eyre::Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> eyre::Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the last block whose logs are fully indexed
+    pub fn last_block(&self) -> eyre::Result<Option<u64>> {
+        self.meta("last_block")?
+            .map(|value| value.parse().wrap_err("corrupt last_block"))
+            .transpose()
+    }
+
+    /// Returns the hashes of indexed blocks, newest first
+    pub fn recorded_blocks(&self) -> eyre::Result<Vec<(u64, B256)>> {
+        let mut stmt = self.conn.prepare("SELECT number, hash FROM blocks ORDER BY number DESC")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        rows.map(|row| {
+            let (number, hash) = row?;
+            Ok((number as u64, hash.parse()?))
+        })
+        .collect()
+    }
+
+    /// Returns the addresses of all known collections
+    pub fn collection_addresses(&self) -> eyre::Result<Vec<Address>> {
+        let mut stmt = self.conn.prepare("SELECT address FROM collections ORDER BY collection_id")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.map(|row| Ok(row?.parse()?)).collect()
+    }
+
+    /// Stores and applies the logs of an indexed block range ending at `last_block`
+    ///
+    /// `logs` must be sorted by (block, log index); `last_hash` is the hash of `last_block`.
+    pub fn commit_range(&mut self, logs: &[RawLog], last_block: u64, last_hash: B256) -> eyre::Result<()> {
+        let tx = self.conn.transaction()?;
+        for log in logs {
+            store_log(&tx, log)?;
+            apply_log(&tx, self.token_factory, self.nft_factory, log)?;
+            record_block(&tx, log.block(), log.block_hash)?;
+        }
+        record_block(&tx, last_block, last_hash)?;
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('last_block', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [last_block.to_string()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Forgets everything indexed after `block` (or everything, for `None`) and
+    /// rebuilds the derived tables from the remaining logs
+    pub fn rollback_to(&mut self, block: Option<u64>) -> eyre::Result<()> {
+        // -1 keeps every block when rolling back to nothing
+        let keep = block.map_or(-1, |block| block as i64);
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM logs WHERE block_number > ?1", [keep])?;
+        tx.execute("DELETE FROM blocks WHERE number > ?1", [keep])?;
+        for table in DERIVED_TABLES {
+            tx.execute(&format!("DELETE FROM {table}"), [])?;
+        }
+
+        let logs = {
+            let mut stmt = tx.prepare(
+                "SELECT block_number, log_index, block_hash, transaction_hash, address, topics, data
+                 FROM logs ORDER BY block_number, log_index",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?;
+            rows.map(|row| {
+                let (number, index, block_hash, tx_hash, address, topics, data) = row?;
+                Ok(RawLog {
+                    address: address.parse()?,
+                    topics: topics
+                        .split(',')
+                        .filter(|topic| !topic.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()?,
+                    data: data.parse::<Bytes>()?,
+                    block_number: U64::from(number as u64),
+                    block_hash: block_hash.parse()?,
+                    transaction_hash: tx_hash.parse()?,
+                    log_index: U64::from(index as u64),
+                    removed: false,
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?
+        };
+        for log in &logs {
+            apply_log(&tx, self.token_factory, self.nft_factory, log)?;
+        }
+
+        match block {
+            Some(block) => {
+                tx.execute(
+                    "INSERT INTO meta (key, value) VALUES ('last_block', ?1)
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                    [block.to_string()],
+                )?;
+            }
+            None => {
+                tx.execute("DELETE FROM meta WHERE key = 'last_block'", [])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns a token by id
+    pub fn token(&self, token_id: u64) -> eyre::Result<Option<TokenRecord>> {
+        self.conn
+            .query_row(
+                "SELECT token_id, creator, name, symbol, decimals, initial_supply, total_supply, created_block
+                 FROM tokens WHERE token_id = ?1",
+                [token_id as i64],
+                token_row,
+            )
+            .optional()?
+            .map(parse_token)
+            .transpose()
+    }
+
+    /// Returns all tokens ordered by id
+    pub fn tokens(&self) -> eyre::Result<Vec<TokenRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT token_id, creator, name, symbol, decimals, initial_supply, total_supply, created_block
+             FROM tokens ORDER BY token_id",
+        )?;
+        let rows = stmt.query_map([], token_row)?;
+        rows.map(|row| parse_token(row?)).collect()
+    }
+
+    /// Returns the balance of `account` in a factory token
+    pub fn balance_of(&self, token_id: u64, account: Address) -> eyre::Result<U256> {
+        balance(&self.conn, token_id as i64, account)
+    }
+
+    /// Returns the (token_id, balance) pairs of every token `account` holds
+    pub fn portfolio(&self, account: Address) -> eyre::Result<Vec<(u64, U256)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT token_id, balance FROM token_balances WHERE account = ?1 ORDER BY token_id")?;
+        let rows = stmt.query_map([hex_address(account)], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.map(|row| {
+            let (token_id, balance) = row?;
+            Ok((token_id as u64, balance.parse()?))
+        })
+        .collect()
+    }
+
+    /// Returns the allowance of `spender` over `owner`'s balance of a factory token
+    pub fn allowance(&self, token_id: u64, owner: Address, spender: Address) -> eyre::Result<U256> {
+        let amount: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT amount FROM token_allowances WHERE token_id = ?1 AND owner = ?2 AND spender = ?3",
+                params![token_id as i64, hex_address(owner), hex_address(spender)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(amount.map(|amount| amount.parse()).transpose()?.unwrap_or_default())
+    }
+
+    /// Returns all collections ordered by id
+    pub fn collections(&self) -> eyre::Result<Vec<CollectionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT collection_id, address, creator, name, symbol, base_uri, created_block
+             FROM collections ORDER BY collection_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (collection_id, address, creator, name, symbol, base_uri, created_block) = row?;
+            Ok(CollectionRecord {
+                collection_id: collection_id as u64,
+                address: address.parse()?,
+                creator: creator.parse()?,
+                name,
+                symbol,
+                base_uri,
+                created_block: created_block as u64,
+            })
+        })
+        .collect()
+    }
+
+    /// Returns the owner of an NFT, if it exists
+    pub fn nft_owner(&self, collection: Address, token_id: u64) -> eyre::Result<Option<Address>> {
+        let owner: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT owner FROM nft_owners WHERE collection = ?1 AND token_id = ?2",
+                params![hex_address(collection), token_id as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(owner.map(|owner| owner.parse()).transpose()?)
+    }
+
+    /// Returns the NFTs of a collection owned by `owner`
+    pub fn nfts_of(&self, collection: Address, owner: Address) -> eyre::Result<Vec<u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT token_id FROM nft_owners WHERE collection = ?1 AND owner = ?2 ORDER BY token_id",
+        )?;
+        let rows = stmt.query_map(params![hex_address(collection), hex_address(owner)], |row| row.get::<_, i64>(0))?;
+        rows.map(|row| Ok(row? as u64)).collect()
+    }
+}
+
+type TokenRow = (i64, String, String, String, i64, String, String, i64);
+
+fn token_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TokenRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+    ))
+}
+
+fn parse_token(row: TokenRow) -> eyre::Result<TokenRecord> {
+    let (token_id, creator, name, symbol, decimals, initial_supply, total_supply, created_block) = row;
+    Ok(TokenRecord {
+        token_id: token_id as u64,
+        creator: creator.parse()?,
+        name,
+        symbol,
+        decimals: decimals as u8,
+        initial_supply: initial_supply.parse()?,
+        total_supply: total_supply.parse()?,
+        created_block: created_block as u64,
+    })
+}
+
+fn hex_address(address: Address) -> String {
+    format!("{address:#x}")
+}
+
+/// Converts an on-chain counter id to an SQLite integer key
+fn sql_id(id: U256) -> eyre::Result<i64> {
+    // The TokenFactory's WETH, read back as `WETH_TOKEN_ID`
+    if id == U256::MAX {
+        return Ok(-1);
+    }
+    i64::try_from(id).map_err(|_| eyre!("id {id} does not fit the registry"))
+}
+
+fn store_log(conn: &Connection, log: &RawLog) -> eyre::Result<()> {
+    let topics = log.topics.iter().map(|topic| topic.to_string()).collect::<Vec<_>>().join(",");
+    conn.execute(
+        "INSERT INTO logs (block_number, log_index, block_hash, transaction_hash, address, topics, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            log.block() as i64,
+            log.index() as i64,
+            log.block_hash.to_string(),
+            log.transaction_hash.to_string(),
+            hex_address(log.address),
+            topics,
+            log.data.to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn record_block(conn: &Connection, number: u64, hash: B256) -> eyre::Result<()> {
+    conn.execute(
+        "INSERT INTO blocks (number, hash) VALUES (?1, ?2)
+         ON CONFLICT(number) DO UPDATE SET hash = excluded.hash",
+        params![number as i64, hash.to_string()],
+    )?;
+    Ok(())
+}
+
+fn emitter(conn: &Connection, token_factory: Address, nft_factory: Address, address: Address) -> eyre::Result<Option<Emitter>> {
+    if address == token_factory {
+        return Ok(Some(Emitter::TokenFactory));
+    }
+    if address == nft_factory {
+        return Ok(Some(Emitter::NftFactory));
+    }
+    let known: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM collections WHERE address = ?1)",
+        [hex_address(address)],
+        |row| row.get(0),
+    )?;
+    Ok(known.then_some(Emitter::Collection))
+}
+
+fn apply_log(conn: &Connection, token_factory: Address, nft_factory: Address, log: &RawLog) -> eyre::Result<()> {
+    let Some(emitter) = emitter(conn, token_factory, nft_factory, log.address)? else {
+        return Ok(());
+    };
+    let event = decode(emitter, log)
+        .wrap_err_with(|| format!("failed to decode log {} of block {}", log.index(), log.block()))?;
+    match event {
+        Some(event) => apply_event(conn, log.block(), event),
+        None => Ok(()),
+    }
+}
+
+fn apply_event(conn: &Connection, block: u64, event: RegistryEvent) -> eyre::Result<()> {
+    match event {
+        RegistryEvent::TokenCreated { token_id, creator, name, symbol, decimals, initial_supply } => {
+            // The initial supply is accounted for by the mint `Transfer` that follows
+            conn.execute(
+                "INSERT INTO tokens (token_id, creator, name, symbol, decimals, initial_supply, total_supply, created_block)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, '0', ?7)",
+                params![
+                    sql_id(token_id)?,
+                    hex_address(creator),
+                    name,
+                    symbol,
+                    sql_id(decimals)?,
+                    initial_supply.to_string(),
+                    block as i64,
+                ],
+            )?;
+        }
+        RegistryEvent::TokenRenamed { token_id, name, symbol } => {
+            conn.execute(
+                "UPDATE tokens SET name = ?2, symbol = ?3 WHERE token_id = ?1",
+                params![sql_id(token_id)?, name, symbol],
+            )?;
+        }
+        RegistryEvent::CreatorTransferred { token_id, new_creator } => {
+            conn.execute(
+                "UPDATE tokens SET creator = ?2 WHERE token_id = ?1",
+                params![sql_id(token_id)?, hex_address(new_creator)],
+            )?;
+        }
+        RegistryEvent::TokenTransfer { token_id, from, to, value } => {
+            let token_id = sql_id(token_id)?;
+            if from == Address::ZERO {
+                adjust_supply(conn, token_id, value, true)?;
+            } else {
+                let have = balance(conn, token_id, from)?;
+                let left = have
+                    .checked_sub(value)
+                    .ok_or_else(|| eyre!("transfer of {value} exceeds indexed balance {have} of {from}"))?;
+                set_balance(conn, token_id, from, left)?;
+            }
+            if to == Address::ZERO {
+                adjust_supply(conn, token_id, value, false)?;
+            } else {
+                let have = balance(conn, token_id, to)?;
+                set_balance(conn, token_id, to, have + value)?;
+            }
+        }
+        RegistryEvent::TokenApproval { token_id, owner, spender, value } => {
+            conn.execute(
+                "INSERT INTO token_allowances (token_id, owner, spender, amount) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(token_id, owner, spender) DO UPDATE SET amount = excluded.amount",
+                params![sql_id(token_id)?, hex_address(owner), hex_address(spender), value.to_string()],
+            )?;
+        }
+        RegistryEvent::CollectionCreated { collection_id, collection, creator, name, symbol, base_uri } => {
+            conn.execute(
+                "INSERT INTO collections (collection_id, address, creator, name, symbol, base_uri, created_block)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    sql_id(collection_id)?,
+                    hex_address(collection),
+                    hex_address(creator),
+                    name,
+                    symbol,
+                    base_uri,
+                    block as i64,
+                ],
+            )?;
+        }
+        RegistryEvent::NftTransfer { collection, token_id, to, .. } => {
+            if to == Address::ZERO {
+                conn.execute(
+                    "DELETE FROM nft_owners WHERE collection = ?1 AND token_id = ?2",
+                    params![hex_address(collection), sql_id(token_id)?],
+                )?;
+            } else {
+                conn.execute(
+                    "INSERT INTO nft_owners (collection, token_id, owner) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(collection, token_id) DO UPDATE SET owner = excluded.owner",
+                    params![hex_address(collection), sql_id(token_id)?, hex_address(to)],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn balance(conn: &Connection, token_id: i64, account: Address) -> eyre::Result<U256> {
+    let balance: Option<String> = conn
+        .query_row(
+            "SELECT balance FROM token_balances WHERE token_id = ?1 AND account = ?2",
+            params![token_id, hex_address(account)],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(balance.map(|balance| balance.parse()).transpose()?.unwrap_or_default())
+}
+
+fn set_balance(conn: &Connection, token_id: i64, account: Address, balance: U256) -> eyre::Result<()> {
+    if balance.is_zero() {
+        conn.execute(
+            "DELETE FROM token_balances WHERE token_id = ?1 AND account = ?2",
+            params![token_id, hex_address(account)],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT INTO token_balances (token_id, account, balance) VALUES (?1, ?2, ?3)
+             ON CONFLICT(token_id, account) DO UPDATE SET balance = excluded.balance",
+            params![token_id, hex_address(account), balance.to_string()],
+        )?;
+    }
+    Ok(())
+}
+
+fn adjust_supply(conn: &Connection, token_id: i64, amount: U256, mint: bool) -> eyre::Result<()> {
+    let supply: Option<String> = conn
+        .query_row("SELECT total_supply FROM tokens WHERE token_id = ?1", [token_id], |row| row.get(0))
+        .optional()?;
+    let Some(supply) = supply else {
+        bail!("transfer for unknown token {token_id}");
+    };
+    let supply: U256 = supply.parse()?;
+    let supply = if mint {
+        supply + amount
+    } else {
+        supply
+            .checked_sub(amount)
+            .ok_or_else(|| eyre!("burn of {amount} exceeds indexed supply {supply} of token {token_id}"))?
+    };
+    conn.execute(
+        "UPDATE tokens SET total_supply = ?2 WHERE token_id = ?1",
+        params![token_id, supply.to_string()],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Decoding raw logs into registry events
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::SolEvent;
+
+use crate::abi::{nft_factory, token_factory};
+use crate::log::RawLog;
+
+/// The kind of contract a log was emitted by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emitter {
+    TokenFactory,
+    NftFactory,
+    Collection,
+}
+
+/// An event the registry folds into its tables
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistryEvent {
+    TokenCreated {
+        token_id: U256,
+        creator: Address,
+        name: String,
+        symbol: String,
+        decimals: U256,
+        initial_supply: U256,
+    },
+    TokenRenamed {
+        token_id: U256,
+        name: String,
+        symbol: String,
+    },
+    CreatorTransferred {
+        token_id: U256,
+        new_creator: Address,
+    },
+    TokenTransfer {
+        token_id: U256,
+        from: Address,
+        to: Address,
+        value: U256,
+    },
+    TokenApproval {
+        token_id: U256,
+        owner: Address,
+        spender: Address,
+        value: U256,
+    },
+    CollectionCreated {
+        collection_id: U256,
+        collection: Address,
+        creator: Address,
+        name: String,
+        symbol: String,
+        base_uri: String,
+    },
+    NftTransfer {
+        collection: Address,
+        token_id: U256,
+        from: Address,
+        to: Address,
+    },
+}
+
+/// Decodes a log emitted by `emitter`
+///
+/// Returns `Ok(None)` for events the registry does not track.
+pub fn decode(emitter: Emitter, log: &RawLog) -> Result<Option<RegistryEvent>, alloy_sol_types::Error> {
+    let Some(&topic0) = log.topics.first() else {
+        return Ok(None);
+    };
+
+    let event = match emitter {
+        Emitter::TokenFactory => match topic0 {
+            token_factory::TokenCreated::SIGNATURE_HASH => {
+                let event = decode_log::<token_factory::TokenCreated>(log)?;
+                RegistryEvent::TokenCreated {
+                    token_id: event.token_id,
+                    creator: event.creator,
+                    name: bytes32_to_string(event.name),
+                    symbol: bytes32_to_string(event.symbol),
+                    decimals: event.decimals,
+                    initial_supply: event.initial_supply,
+                }
+            }
+            token_factory::TokenRenamed::SIGNATURE_HASH => {
+                let event = decode_log::<token_factory::TokenRenamed>(log)?;
+                RegistryEvent::TokenRenamed {
+                    token_id: event.token_id,
+                    name: bytes32_to_string(event.name),
+                    symbol: bytes32_to_string(event.symbol),
+                }
+            }
+            token_factory::CreatorTransferred::SIGNATURE_HASH => {
+                let event = decode_log::<token_factory::CreatorTransferred>(log)?;
+                RegistryEvent::CreatorTransferred { token_id: event.token_id, new_creator: event.new_creator }
+            }
+            token_factory::Transfer::SIGNATURE_HASH => {
+                let event = decode_log::<token_factory::Transfer>(log)?;
+                RegistryEvent::TokenTransfer {
+                    token_id: event.token_id,
+                    from: event.from,
+                    to: event.to,
+                    value: event.value,
+                }
+            }
+            token_factory::Approval::SIGNATURE_HASH => {
+                let event = decode_log::<token_factory::Approval>(log)?;
+                RegistryEvent::TokenApproval {
+                    token_id: event.token_id,
+                    owner: event.owner,
+                    spender: event.spender,
+                    value: event.value,
+                }
+            }
+            _ => return Ok(None),
+        },
+        Emitter::NftFactory => match topic0 {
+            nft_factory::CollectionCreated::SIGNATURE_HASH => {
+                let event = decode_log::<nft_factory::CollectionCreated>(log)?;
+                RegistryEvent::CollectionCreated {
+                    collection_id: event.collection_id,
+                    collection: event.collection_address,
+                    creator: event.creator,
+                    name: event.name,
+                    symbol: event.symbol,
+                    base_uri: event.base_uri,
+                }
+            }
+            _ => return Ok(None),
+        },
+        Emitter::Collection => match topic0 {
+            nft_factory::Transfer::SIGNATURE_HASH => {
+                let event = decode_log::<nft_factory::Transfer>(log)?;
+                RegistryEvent::NftTransfer {
+                    collection: log.address,
+                    token_id: event.token_id,
+                    from: event.from,
+                    to: event.to,
+                }
+            }
+            _ => return Ok(None),
+        },
+    };
+
+    Ok(Some(event))
+}
+
+fn decode_log<E: SolEvent>(log: &RawLog) -> Result<E, alloy_sol_types::Error> {
+    E::decode_raw_log(log.topics.iter().copied(), &log.data, true)
+}
+
+/// Converts a right-padded `bytes32` string (as stored by the TokenFactory) to a `String`
+pub fn bytes32_to_string(value: B256) -> String {
+    let len = value.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&value[..len]).into_owned()
+}
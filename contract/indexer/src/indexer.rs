@@ -0,0 +1,114 @@
+//! Following the chain and feeding logs into the registry
+
+use alloy_primitives::Address;
+use eyre::{bail, eyre};
+
+use crate::db::Registry;
+use crate::decode::{decode, Emitter, RegistryEvent};
+use crate::source::LogSource;
+
+/// What to index and how
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub token_factory: Address,
+    pub nft_factory: Address,
+    /// First block to index when the database is empty
+    pub start_block: u64,
+    /// Maximum number of blocks requested per `eth_getLogs` call
+    pub batch_size: u64,
+    /// Number of blocks to stay behind the chain head
+    pub confirmations: u64,
+}
+
+/// Folds factory logs from a [`LogSource`] into a [`Registry`]
+pub struct Indexer<S> {
+    source: S,
+    registry: Registry,
+    config: Config,
+}
+
+impl<S: LogSource> Indexer<S> {
+    pub fn new(source: S, registry: Registry, config: Config) -> Self {
+        Self { source, registry, config }
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Indexes up to the confirmed chain head, undoing orphaned blocks first
+    ///
+    /// Returns the last indexed block, if any.
+    pub async fn sync(&mut self) -> eyre::Result<Option<u64>> {
+        self.handle_reorg().await?;
+
+        let head = self.source.latest_block().await?;
+        let target = head.saturating_sub(self.config.confirmations);
+        let batch_size = self.config.batch_size.max(1);
+
+        let mut from = match self.registry.last_block()? {
+            Some(last) => last + 1,
+            None => self.config.start_block,
+        };
+        while from <= target {
+            let to = target.min(from + batch_size - 1);
+            self.index_range(from, to).await?;
+            from = to + 1;
+        }
+
+        self.registry.last_block()
+    }
+
+    /// Rolls the registry back to the newest indexed block that is still canonical
+    async fn handle_reorg(&mut self) -> eyre::Result<()> {
+        let Some(last) = self.registry.last_block()? else {
+            return Ok(());
+        };
+
+        for (number, hash) in self.registry.recorded_blocks()? {
+            if self.source.block_hash(number).await? == Some(hash) {
+                if number < last {
+                    self.registry.rollback_to(Some(number))?;
+                }
+                return Ok(());
+            }
+        }
+
+        // Nothing we indexed is canonical anymore
+        self.registry.rollback_to(None)
+    }
+
+    async fn index_range(&mut self, from: u64, to: u64) -> eyre::Result<()> {
+        let factories = [self.config.token_factory, self.config.nft_factory];
+        let mut logs = self.source.logs(&factories, from, to).await?;
+
+        // Collections created in this range emit their first logs in it too
+        let mut collections = self.registry.collection_addresses()?;
+        for log in logs.iter().filter(|log| log.address == self.config.nft_factory && !log.removed) {
+            if let Some(RegistryEvent::CollectionCreated { collection, .. }) = decode(Emitter::NftFactory, log)? {
+                collections.push(collection);
+            }
+        }
+        if !collections.is_empty() {
+            logs.extend(self.source.logs(&collections, from, to).await?);
+        }
+
+        logs.retain(|log| !log.removed);
+        logs.sort_by_key(|log| (log.block(), log.index()));
+
+        let last_hash = self
+            .source
+            .block_hash(to)
+            .await?
+            .ok_or_else(|| eyre!("block {to} not found"))?;
+        if logs.iter().any(|log| log.block() == to && log.block_hash != last_hash) {
+            bail!("block {to} was reorganized while indexing");
+        }
+
+        self.registry.commit_range(&logs, to, last_hash)
+    }
+}
@@ -0,0 +1,26 @@
+//!
+//! Registry indexer for the Stylus TokenFactory and NftFactory
+//!
+//! Folds `TokenCreated`, `TokenRenamed`, `CreatorTransferred`, `Transfer`, `Approval` and
+//! `CollectionCreated` logs
+//! (plus the `Transfer` logs of every collection the NftFactory deploys) into a
+//! SQLite database of tokens, balances, allowances, collections and NFT owners.
+//!
+//! Logs are decoded with the same `sol!` definitions the contracts emit them
+//! with, see [`abi`]. Every indexed log is kept in the database so that a chain
+//! reorganization can be undone by dropping the orphaned logs and replaying the
+//! rest.
+//!
+
+pub mod abi;
+pub mod db;
+pub mod decode;
+pub mod indexer;
+pub mod log;
+pub mod source;
+
+pub use db::{Registry, WETH_TOKEN_ID};
+pub use decode::RegistryEvent;
+pub use indexer::{Config, Indexer};
+pub use log::RawLog;
+pub use source::{LogSource, RecordedChain, RpcSource};
@@ -0,0 +1,31 @@
+//! Raw logs as returned by `eth_getLogs`
+
+use alloy_primitives::{Address, Bytes, B256, U64};
+use serde::{Deserialize, Serialize};
+
+/// A log in the JSON-RPC representation, as returned by `eth_getLogs`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    pub block_number: U64,
+    pub block_hash: B256,
+    pub transaction_hash: B256,
+    pub log_index: U64,
+    #[serde(default)]
+    pub removed: bool,
+}
+
+impl RawLog {
+    /// Returns the block number as a plain integer
+    pub fn block(&self) -> u64 {
+        self.block_number.to::<u64>()
+    }
+
+    /// Returns the position of this log in its block
+    pub fn index(&self) -> u64 {
+        self.log_index.to::<u64>()
+    }
+}
@@ -0,0 +1,88 @@
+//! Indexes TokenFactory and NftFactory events into a SQLite registry.
+//!
+//! Usage:
+//!   registry-indexer --rpc-url <URL> --db <PATH> --token-factory <ADDRESS> --nft-factory <ADDRESS>
+//!                    [--from-block <N>] [--confirmations <N>] [--batch-size <N>]
+//!                    [--poll-interval <SECONDS>] [--once]
+//!
+//! Every option can also be given through the environment (or a `.env` file):
+//! RPC_URL, DATABASE_PATH, TOKEN_FACTORY_ADDRESS, NFT_FACTORY_ADDRESS, FROM_BLOCK,
+//! CONFIRMATIONS, BATCH_SIZE and POLL_INTERVAL.
+//! Indexing resumes from the last indexed block stored in the database.
+
+use std::time::Duration;
+
+use dotenv::dotenv;
+use eyre::{eyre, WrapErr};
+use registry_indexer::{Config, Indexer, Registry, RpcSource};
+
+struct Args {
+    rpc_url: String,
+    db: String,
+    config: Config,
+    poll_interval: Duration,
+    once: bool,
+}
+
+fn parse_args() -> eyre::Result<Args> {
+    let mut options = std::collections::HashMap::new();
+    let mut once = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--once" => once = true,
+            flag if flag.starts_with("--") => {
+                let value = args.next().ok_or_else(|| eyre!("missing value for {flag}"))?;
+                options.insert(flag.trim_start_matches("--").to_string(), value);
+            }
+            other => return Err(eyre!("unexpected argument {other}")),
+        }
+    }
+
+    let option = |flag: &str, env: &str| options.get(flag).cloned().or_else(|| std::env::var(env).ok());
+    let required = |flag: &str, env: &str| option(flag, env).ok_or_else(|| eyre!("--{flag} (or {env}) is required"));
+    let number = |flag: &str, env: &str, default: u64| -> eyre::Result<u64> {
+        option(flag, env)
+            .map(|value| value.parse().wrap_err_with(|| format!("invalid --{flag}")))
+            .transpose()
+            .map(|value| value.unwrap_or(default))
+    };
+
+    Ok(Args {
+        rpc_url: required("rpc-url", "RPC_URL")?,
+        db: option("db", "DATABASE_PATH").unwrap_or_else(|| "registry.db".to_string()),
+        config: Config {
+            token_factory: required("token-factory", "TOKEN_FACTORY_ADDRESS")?.parse()?,
+            nft_factory: required("nft-factory", "NFT_FACTORY_ADDRESS")?.parse()?,
+            start_block: number("from-block", "FROM_BLOCK", 0)?,
+            batch_size: number("batch-size", "BATCH_SIZE", 2_000)?,
+            confirmations: number("confirmations", "CONFIRMATIONS", 0)?,
+        },
+        poll_interval: Duration::from_secs(number("poll-interval", "POLL_INTERVAL", 5)?),
+        once,
+    })
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    dotenv().ok();
+    let args = parse_args()?;
+
+    let registry = Registry::open(&args.db, args.config.token_factory, args.config.nft_factory)?;
+    let source = RpcSource::new(&args.rpc_url)?;
+    let mut indexer = Indexer::new(source, registry, args.config);
+
+    loop {
+        match indexer.sync().await {
+            Ok(Some(block)) => println!("Indexed up to block {block}"),
+            Ok(None) => println!("Nothing to index yet"),
+            Err(err) if !args.once => eprintln!("Indexing failed, retrying: {err:?}"),
+            Err(err) => return Err(err),
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(args.poll_interval).await;
+    }
+}
@@ -0,0 +1,120 @@
+//! Where the indexer reads blocks and logs from
+
+use std::path::Path;
+
+use alloy_primitives::{Address, Bytes, B256, U64};
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::{Filter, Log, H160},
+};
+use eyre::{eyre, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::log::RawLog;
+
+/// A view of the chain the indexer follows
+#[allow(async_fn_in_trait)]
+pub trait LogSource {
+    /// Returns the number of the latest block
+    async fn latest_block(&self) -> eyre::Result<u64>;
+
+    /// Returns the hash of a canonical block, if it exists
+    async fn block_hash(&self, number: u64) -> eyre::Result<Option<B256>>;
+
+    /// Returns the logs emitted by `addresses` in blocks `from..=to`
+    async fn logs(&self, addresses: &[Address], from: u64, to: u64) -> eyre::Result<Vec<RawLog>>;
+}
+
+/// Reads the chain from a JSON-RPC endpoint
+pub struct RpcSource {
+    provider: Provider<Http>,
+}
+
+impl RpcSource {
+    pub fn new(rpc_url: &str) -> eyre::Result<Self> {
+        let provider = Provider::<Http>::try_from(rpc_url).wrap_err("invalid RPC url")?;
+        Ok(Self { provider })
+    }
+}
+
+impl LogSource for RpcSource {
+    async fn latest_block(&self) -> eyre::Result<u64> {
+        Ok(self.provider.get_block_number().await?.as_u64())
+    }
+
+    async fn block_hash(&self, number: u64) -> eyre::Result<Option<B256>> {
+        let block = self.provider.get_block(number).await?;
+        Ok(block.and_then(|block| block.hash).map(|hash| B256::from(hash.0)))
+    }
+
+    async fn logs(&self, addresses: &[Address], from: u64, to: u64) -> eyre::Result<Vec<RawLog>> {
+        let addresses: Vec<H160> = addresses.iter().map(|address| H160::from(address.0 .0)).collect();
+        let filter = Filter::new().address(addresses).from_block(from).to_block(to);
+        let logs = self.provider.get_logs(&filter).await?;
+        logs.into_iter().map(convert_log).collect()
+    }
+}
+
+fn convert_log(log: Log) -> eyre::Result<RawLog> {
+    let pending = || eyre!("received a pending log");
+    Ok(RawLog {
+        address: Address::from(log.address.0),
+        topics: log.topics.iter().map(|topic| B256::from(topic.0)).collect(),
+        data: Bytes::from(log.data.to_vec()),
+        block_number: U64::from(log.block_number.ok_or_else(pending)?.as_u64()),
+        block_hash: B256::from(log.block_hash.ok_or_else(pending)?.0),
+        transaction_hash: B256::from(log.transaction_hash.ok_or_else(pending)?.0),
+        log_index: U64::from(log.log_index.ok_or_else(pending)?.as_u64()),
+        removed: log.removed.unwrap_or(false),
+    })
+}
+
+/// A block header as recorded in a chain fixture
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedBlock {
+    pub number: U64,
+    pub hash: B256,
+}
+
+/// A chain recorded to JSON (canonical block hashes plus `eth_getLogs` output),
+/// used to replay indexing without a node
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecordedChain {
+    pub blocks: Vec<RecordedBlock>,
+    pub logs: Vec<RawLog>,
+}
+
+impl RecordedChain {
+    pub fn from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> eyre::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl LogSource for RecordedChain {
+    async fn latest_block(&self) -> eyre::Result<u64> {
+        Ok(self.blocks.iter().map(|block| block.number.to::<u64>()).max().unwrap_or_default())
+    }
+
+    async fn block_hash(&self, number: u64) -> eyre::Result<Option<B256>> {
+        Ok(self
+            .blocks
+            .iter()
+            .find(|block| block.number.to::<u64>() == number)
+            .map(|block| block.hash))
+    }
+
+    async fn logs(&self, addresses: &[Address], from: u64, to: u64) -> eyre::Result<Vec<RawLog>> {
+        Ok(self
+            .logs
+            .iter()
+            .filter(|log| addresses.contains(&log.address) && (from..=to).contains(&log.block()))
+            .cloned()
+            .collect())
+    }
+}
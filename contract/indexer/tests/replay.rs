@@ -0,0 +1,229 @@
+//! Replays recorded chains through the indexer
+//!
+//! `chain.json` holds blocks 100..=110 of a dev chain with two tokens and one
+//! collection; `chain_reorged.json` is the same chain with blocks 106.. replaced.
+
+use alloy_primitives::{address, Address, B256, U256, U64};
+use alloy_sol_types::SolEvent;
+use registry_indexer::{
+    abi::token_factory::{CreatorTransferred, TokenCreated, TokenRenamed, Transfer},
+    Config, Indexer, RecordedChain, Registry, WETH_TOKEN_ID,
+};
+
+const TOKEN_FACTORY: Address = address!("5fbdb2315678afecb367f032d93f642f64180aa3");
+const NFT_FACTORY: Address = address!("e7f1725e7734ce288f8367e1bb143e90bb3f0512");
+const COLLECTION: Address = address!("a16e02e87b7454126e5e10d957a927a7f5b5d2be");
+const ALICE: Address = address!("f39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+const BOB: Address = address!("70997970c51812dc3a010c7d01b50e0d17dc79c8");
+const CAROL: Address = address!("3c44cdddb6a900fa2b585dd299e03d12fa4293bc");
+
+/// Appends a TokenFactory event to the last recorded log's block
+fn append(chain: &mut RecordedChain, event: &impl SolEvent) {
+    let mut log = chain.logs.last().unwrap().clone();
+    log.address = TOKEN_FACTORY;
+    log.topics = event.encode_topics().into_iter().map(|topic| topic.0).collect();
+    log.data = event.encode_data().into();
+    log.log_index += U64::from(1);
+    chain.logs.push(log);
+}
+
+fn fixture(name: &str) -> RecordedChain {
+    RecordedChain::from_file(format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+fn config(batch_size: u64) -> Config {
+    Config {
+        token_factory: TOKEN_FACTORY,
+        nft_factory: NFT_FACTORY,
+        start_block: 100,
+        batch_size,
+        confirmations: 0,
+    }
+}
+
+fn in_memory() -> Registry {
+    Registry::in_memory(TOKEN_FACTORY, NFT_FACTORY).unwrap()
+}
+
+/// Keeps only the blocks and logs up to `last`
+fn truncate(mut chain: RecordedChain, last: u64) -> RecordedChain {
+    chain.blocks.retain(|block| block.number.to::<u64>() <= last);
+    chain.logs.retain(|log| log.block() <= last);
+    chain
+}
+
+fn temp_db(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("registry-indexer-{}-{name}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Everything a registry knows, for comparing two of them
+fn snapshot(registry: &Registry) -> String {
+    let mut out = format!("{:?}\n{:?}\n", registry.tokens().unwrap(), registry.collections().unwrap());
+    for account in [ALICE, BOB, CAROL] {
+        out += &format!("{account}: {:?} {:?}\n", registry.portfolio(account).unwrap(), registry.nfts_of(COLLECTION, account).unwrap());
+    }
+    out
+}
+
+#[tokio::test]
+async fn indexes_recorded_chain() {
+    let mut indexer = Indexer::new(fixture("chain.json"), in_memory(), config(1_000));
+    assert_eq!(indexer.sync().await.unwrap(), Some(110));
+
+    let registry = indexer.registry();
+    let tokens = registry.tokens().unwrap();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!((tokens[0].name.as_str(), tokens[0].symbol.as_str(), tokens[0].decimals), ("MyToken", "MTK", 18));
+    assert_eq!((tokens[1].name.as_str(), tokens[1].symbol.as_str(), tokens[1].decimals), ("HerToken", "HTK", 6));
+    assert_eq!(tokens[1].creator, BOB);
+    assert_eq!(tokens[0].total_supply, U256::from(1_000_000));
+    assert_eq!(tokens[0].created_block, 101);
+
+    assert_eq!(registry.balance_of(0, ALICE).unwrap(), U256::from(710_000));
+    assert_eq!(registry.balance_of(0, BOB).unwrap(), U256::from(250_000));
+    assert_eq!(registry.balance_of(0, CAROL).unwrap(), U256::from(40_000));
+    assert_eq!(registry.balance_of(1, BOB).unwrap(), U256::from(375_000));
+    assert_eq!(registry.portfolio(CAROL).unwrap(), vec![(0, U256::from(40_000)), (1, U256::from(125_000))]);
+    assert_eq!(registry.allowance(0, ALICE, CAROL).unwrap(), U256::from(60_000));
+
+    let collections = registry.collections().unwrap();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0].address, COLLECTION);
+    assert_eq!(collections[0].creator, ALICE);
+    assert_eq!(collections[0].base_uri, "ipfs://art/");
+
+    assert_eq!(registry.nft_owner(COLLECTION, 1).unwrap(), Some(CAROL));
+    assert_eq!(registry.nft_owner(COLLECTION, 2).unwrap(), None);
+}
+
+#[tokio::test]
+async fn follows_token_renames() {
+    let bytes32 = |value: &str| {
+        let mut bytes = [0u8; 32];
+        bytes[..value.len()].copy_from_slice(value.as_bytes());
+        B256::from(bytes)
+    };
+
+    // Append a rename of the first token to the last recorded log's block
+    let mut chain = fixture("chain.json");
+    append(&mut chain, &TokenRenamed { token_id: U256::ZERO, name: bytes32("MyToken v2"), symbol: bytes32("MTK2") });
+
+    let mut indexer = Indexer::new(chain, in_memory(), config(1_000));
+    indexer.sync().await.unwrap();
+
+    let token = indexer.registry().token(0).unwrap().unwrap();
+    assert_eq!((token.name.as_str(), token.symbol.as_str()), ("MyToken v2", "MTK2"));
+    assert_eq!(token.total_supply, U256::from(1_000_000));
+}
+
+#[tokio::test]
+async fn follows_creator_transfers() {
+    let multisig = Address::repeat_byte(0x5a);
+
+    // Append a handover of the first token to a multisig
+    let mut chain = fixture("chain.json");
+    append(&mut chain, &CreatorTransferred { token_id: U256::ZERO, previous_creator: ALICE, new_creator: multisig });
+
+    let mut indexer = Indexer::new(chain, in_memory(), config(1_000));
+    indexer.sync().await.unwrap();
+
+    assert_eq!(indexer.registry().token(0).unwrap().unwrap().creator, multisig);
+}
+
+#[tokio::test]
+async fn indexes_weth() {
+    // The factory registers WETH under the last token id, then wraps ETH for Alice
+    let mut chain = fixture("chain.json");
+    let weth = U256::MAX;
+    append(&mut chain, &TokenCreated {
+        creator: TOKEN_FACTORY,
+        token_id: weth,
+        name: B256::right_padding_from(b"Wrapped Ether"),
+        symbol: B256::right_padding_from(b"WETH"),
+        decimals: U256::from(18),
+        initial_supply: U256::ZERO,
+    });
+    append(&mut chain, &Transfer { token_id: weth, from: Address::ZERO, to: ALICE, value: U256::from(5) });
+
+    let mut indexer = Indexer::new(chain, in_memory(), config(1_000));
+    indexer.sync().await.unwrap();
+
+    let token = indexer.registry().token(WETH_TOKEN_ID).unwrap().unwrap();
+    assert_eq!((token.symbol.as_str(), token.total_supply), ("WETH", U256::from(5)));
+    assert_eq!(indexer.registry().balance_of(WETH_TOKEN_ID, ALICE).unwrap(), U256::from(5));
+}
+
+#[tokio::test]
+async fn small_batches_match_a_single_pass() {
+    let mut single = Indexer::new(fixture("chain.json"), in_memory(), config(1_000));
+    single.sync().await.unwrap();
+
+    let mut batched = Indexer::new(fixture("chain.json"), in_memory(), config(2));
+    batched.sync().await.unwrap();
+
+    assert_eq!(snapshot(batched.registry()), snapshot(single.registry()));
+}
+
+#[tokio::test]
+async fn resumes_from_last_indexed_block() {
+    let path = temp_db("resume");
+
+    let registry = Registry::open(&path, TOKEN_FACTORY, NFT_FACTORY).unwrap();
+    let mut indexer = Indexer::new(truncate(fixture("chain.json"), 103), registry, config(1_000));
+    assert_eq!(indexer.sync().await.unwrap(), Some(103));
+    drop(indexer);
+
+    let registry = Registry::open(&path, TOKEN_FACTORY, NFT_FACTORY).unwrap();
+    assert_eq!(registry.last_block().unwrap(), Some(103));
+    let mut indexer = Indexer::new(fixture("chain.json"), registry, config(1_000));
+    assert_eq!(indexer.sync().await.unwrap(), Some(110));
+
+    let mut fresh = Indexer::new(fixture("chain.json"), in_memory(), config(1_000));
+    fresh.sync().await.unwrap();
+    assert_eq!(snapshot(indexer.registry()), snapshot(fresh.registry()));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn stays_behind_unconfirmed_blocks() {
+    let mut config = config(1_000);
+    config.confirmations = 5;
+
+    let mut indexer = Indexer::new(fixture("chain.json"), in_memory(), config);
+    assert_eq!(indexer.sync().await.unwrap(), Some(105));
+    assert_eq!(indexer.registry().nft_owner(COLLECTION, 1).unwrap(), Some(ALICE));
+}
+
+#[tokio::test]
+async fn rolls_back_reorganized_blocks() {
+    let mut indexer = Indexer::new(fixture("chain.json"), in_memory(), config(1_000));
+    indexer.sync().await.unwrap();
+
+    *indexer.source_mut() = fixture("chain_reorged.json");
+    assert_eq!(indexer.sync().await.unwrap(), Some(109));
+
+    let registry = indexer.registry();
+    assert_eq!(registry.balance_of(0, BOB).unwrap(), U256::from(200_000));
+    assert_eq!(registry.balance_of(0, CAROL).unwrap(), U256::from(90_000));
+    assert_eq!(registry.balance_of(1, BOB).unwrap(), U256::from(500_000));
+    assert_eq!(registry.balance_of(1, CAROL).unwrap(), U256::ZERO);
+    assert_eq!(registry.nft_owner(COLLECTION, 1).unwrap(), Some(BOB));
+    assert_eq!(registry.nft_owner(COLLECTION, 2).unwrap(), None);
+
+    let mut fresh = Indexer::new(fixture("chain_reorged.json"), in_memory(), config(1_000));
+    fresh.sync().await.unwrap();
+    assert_eq!(snapshot(registry), snapshot(fresh.registry()));
+}
+
+#[tokio::test]
+async fn refuses_a_database_of_another_deployment() {
+    let path = temp_db("bind");
+
+    Registry::open(&path, TOKEN_FACTORY, NFT_FACTORY).unwrap();
+    assert!(Registry::open(&path, TOKEN_FACTORY, COLLECTION).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -1,16 +1,46 @@
-use super::*;
-use stylus_sdk::prelude::*;
+//!
+//! Stylus ERC721 collection template
+//!
+//! Deployed once; the NftFactory clones it (EIP-1167) for every collection
+//! and calls `initialize` on the clone. Built on its own from this crate,
+//! see the NftFactory in `../factory` for the other half.
+//!
+//! The program is ABI-equivalent with Solidity.
+//! To export the ABI, run `cargo stylus export-abi`.
+//!
+//! Note: this code is a template and has not been audited.
+//!
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[cfg(all(target_arch = "wasm32", feature = "export-abi"))]
+compile_error!("`export-abi` builds a native ABI printer, build the wasm without it");
+
+extern crate alloc;
+
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+use erc721_nft_factory::*;
+use stylus_common::{access_control::DEFAULT_ADMIN_ROLE, erc2771, AccessControl};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    alloy_sol_types::SolError,
+    host::VM,
+    prelude::*,
+    ArbResult,
+};
 
 // Define the ERC721 NFT storage
 sol_storage! {
-    #[entrypoint]
     pub struct Erc721 {
         string name;
         string symbol;
         string base_uri;
         uint256 next_token_id;
         address creator;
+        address trusted_forwarder;  // ERC-2771 forwarder set by the factory
         bool initialized;
+        AccessControl access_control;  // The creator administers roles
         
         mapping(uint256 => address) owners;
         mapping(address => uint256) balances;
@@ -19,6 +49,19 @@ sol_storage! {
     }
 }
 
+/// Routes every call, taking the signer a trusted forwarder appended off relayed ones
+#[entrypoint]
+pub fn erc721_entrypoint(input: Vec<u8>, host: VM) -> ArbResult {
+    erc2771::entrypoint::<Erc721>(input, host, |collection, caller| collection.is_trusted_forwarder(caller))
+}
+
+unsafe impl TopLevelStorage for Erc721 {}
+
+#[cfg(feature = "export-abi")]
+pub fn print_from_args() {
+    stylus_sdk::abi::export::print_from_args::<Erc721>();
+}
+
 // ============================================
 // ERC721 COLLECTION IMPLEMENTATION
 // ============================================
@@ -32,6 +75,7 @@ impl Erc721 {
         symbol: String,
         base_uri: String,
         creator: Address,
+        trusted_forwarder: Address,
     ) {
         // Only initialize once
         if self.initialized.get() {
@@ -43,7 +87,44 @@ impl Erc721 {
         self.base_uri.set_str(&base_uri);
         self.next_token_id.set(U256::from(1)); // Start token IDs from 1
         self.creator.set(creator);
+        self.trusted_forwarder.set(trusted_forwarder);
         self.initialized.set(true);
+
+        let factory = self.vm().msg_sender();
+        self.access_control._grant_role(DEFAULT_ADMIN_ROLE, creator, factory);
+    }
+
+    /// Returns true if `account` holds `role`
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.access_control.has_role(role, account)
+    }
+
+    /// Returns the role whose members administer `role`
+    pub fn get_role_admin(&self, role: B256) -> B256 {
+        self.access_control.get_role_admin(role)
+    }
+
+    /// Grants `role` to `account` (admins of `role` only)
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        let caller = self._msg_sender();
+        self.access_control.grant_role(caller, role, account)
+    }
+
+    /// Revokes `role` from `account` (admins of `role` only)
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        let caller = self._msg_sender();
+        self.access_control.revoke_role(caller, role, account)
+    }
+
+    /// Gives up one of the caller's roles, `account` must be the caller
+    pub fn renounce_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        let caller = self._msg_sender();
+        self.access_control.renounce_role(caller, role, account)
+    }
+
+    /// Returns true if `forwarder` may relay calls on behalf of users (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        forwarder != Address::ZERO && forwarder == self.trusted_forwarder.get()
     }
 
     /// Returns the creator of this collection
@@ -128,7 +209,7 @@ impl Erc721 {
     /// Burns (destroys) an NFT
     pub fn burn(&mut self, token_id: U256) -> Result<bool, Vec<u8>> {
         let owner = self.owner_of(token_id)?;
-        let caller = self.vm().msg_sender();
+        let caller = self._msg_sender();
 
         if !self._is_approved_or_owner(caller, token_id) {
             return Err(NotOwnerOrApproved { caller, token_id }.abi_encode());
@@ -160,7 +241,7 @@ impl Erc721 {
         to: Address,
         token_id: U256,
     ) -> Result<bool, Vec<u8>> {
-        let caller = self.vm().msg_sender();
+        let caller = self._msg_sender();
         
         if !self._is_approved_or_owner(caller, token_id) {
             return Err(NotOwnerOrApproved { caller, token_id }.abi_encode());
@@ -183,7 +264,7 @@ impl Erc721 {
     /// Approves another address to transfer a specific token
     pub fn approve(&mut self, to: Address, token_id: U256) -> Result<bool, Vec<u8>> {
         let owner = self.owner_of(token_id)?;
-        let caller = self.vm().msg_sender();
+        let caller = self._msg_sender();
 
         if caller != owner && !self.is_approved_for_all(owner, caller) {
             return Err(NotOwner { caller, token_id }.abi_encode());
@@ -210,7 +291,7 @@ impl Erc721 {
 
     /// Sets approval for all tokens to an operator
     pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<bool, Vec<u8>> {
-        let owner = self.vm().msg_sender();
+        let owner = self._msg_sender();
         self.operator_approvals.setter(owner).setter(operator).set(approved);
 
         log(self.vm(), ApprovalForAll {
@@ -230,6 +311,15 @@ impl Erc721 {
 
 // Internal helper functions
 impl Erc721 {
+    /// Returns the account a call is made on behalf of (ERC-2771)
+    fn _msg_sender(&self) -> Address {
+        let sender = self.vm().msg_sender();
+        if !self.is_trusted_forwarder(sender) {
+            return sender;
+        }
+        erc2771::forwarded_sender().unwrap_or(sender)
+    }
+
     /// Checks if a token exists
     fn _exists(&self, token_id: U256) -> bool {
         self.owners.get(token_id) != Address::ZERO
@@ -287,3 +377,64 @@ impl Erc721 {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{
+        alloy_sol_types::{sol, SolCall, SolValue},
+        testing::*,
+    };
+
+    #[test]
+    fn test_anyone_mints_and_holders_transfer() {
+        let vm = TestVM::default();
+        let mut collection = Erc721::from(&vm);
+        let creator = vm.msg_sender();
+        let alice = Address::from([2u8; 20]);
+
+        collection.initialize("Art".to_string(), "ART".to_string(), "ipfs://art/".to_string(), creator, Address::ZERO);
+        assert!(collection.has_role(DEFAULT_ADMIN_ROLE, creator));
+
+        let token_id = collection.mint(alice).unwrap();
+        assert_eq!(token_id, U256::from(1));
+        assert_eq!(collection.owner_of(token_id).unwrap(), alice);
+        assert_eq!(collection.token_uri(token_id).unwrap(), "ipfs://art/1");
+
+        // Minting stays open to every caller, as before roles were added
+        vm.set_sender(alice);
+        assert_eq!(collection.mint(alice), Ok(U256::from(2)));
+        assert!(collection.transfer_from(alice, creator, token_id).unwrap());
+        assert_eq!(collection.balance_of(creator), U256::from(1));
+        assert_eq!(collection.balance_of(alice), U256::from(1));
+    }
+
+    #[test]
+    fn test_relayed_transfer() {
+        sol! {
+            function transferFrom(address from, address to, uint256 token_id) external returns (bool);
+        }
+
+        let vm = TestVM::default();
+        let mut collection = Erc721::from(&vm);
+        let creator = Address::from([1u8; 20]);
+        let forwarder = Address::from([0xf0u8; 20]);
+        let alice = Address::from([2u8; 20]);
+        collection.initialize("Art".to_string(), "ART".to_string(), "ipfs://art/".to_string(), creator, forwarder);
+        let token_id = collection.mint(alice).unwrap();
+
+        let route = |calldata: Vec<u8>| erc721_entrypoint(calldata, VM { host: Box::new(vm.clone()) });
+        let call = transferFromCall { from: alice, to: creator, token_id }.abi_encode();
+
+        // Relayed for the creator, who neither owns nor was approved for the token
+        vm.set_sender(forwarder);
+        assert_eq!(
+            route([call.as_slice(), creator.as_slice()].concat()),
+            Err(NotOwnerOrApproved { caller: creator, token_id }.abi_encode())
+        );
+
+        // Relayed for alice, who owns it
+        assert_eq!(route([call.as_slice(), alice.as_slice()].concat()), Ok(true.abi_encode()));
+        assert_eq!(collection.owner_of(token_id).unwrap(), creator);
+    }
+}
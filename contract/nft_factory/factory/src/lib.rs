@@ -0,0 +1,389 @@
+//!
+//! Stylus NFT Factory
+//!
+//! Lets any user create their own ERC721 collection: each one is an EIP-1167
+//! clone of the collection template built from `../erc721`, deployed with
+//! CREATE2 and initialized for its creator.
+//!
+//! The program is ABI-equivalent with Solidity.
+//! To export the ABI, run `cargo stylus export-abi`.
+//!
+//! Note: this code is a template and has not been audited.
+//!
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[cfg(all(target_arch = "wasm32", feature = "export-abi"))]
+compile_error!("`export-abi` builds a native ABI printer, build the wasm without it");
+
+extern crate alloc;
+
+use alloc::{string::String, vec, vec::Vec};
+use erc721_nft_factory::*;
+use stylus_common::{clones, erc2771, multicall, Ownable, Pausable, ReentrancyGuard, Rescuable};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256},
+    alloy_sol_types::{sol, SolCall, SolError},
+    call::RawCall,
+    host::VM,
+    prelude::*,
+    ArbResult,
+};
+
+// Define the NFT Factory storage
+sol_storage! {
+    pub struct NftFactory {
+        Ownable ownable;
+        Pausable pausable;  // Stops collection creation while set
+        ReentrancyGuard reentrancy_guard;
+        Rescuable rescuable;  // ETH and ERC20s sent here by mistake
+        address implementation;
+        address trusted_forwarder;  // ERC-2771 forwarder, handed to every new collection
+        uint256 collection_count;
+        mapping(uint256 => address) collections;
+        mapping(address => address[]) creator_to_collections;
+        mapping(address => uint256) collection_to_id;
+    }
+}
+
+/// Routes every call, taking the signer a trusted forwarder appended off relayed ones
+#[entrypoint]
+pub fn nft_factory_entrypoint(input: Vec<u8>, host: VM) -> ArbResult {
+    erc2771::entrypoint::<NftFactory>(input, host, |factory, caller| factory.is_trusted_forwarder(caller))
+}
+
+unsafe impl TopLevelStorage for NftFactory {}
+
+#[cfg(feature = "export-abi")]
+pub fn print_from_args() {
+    stylus_sdk::abi::export::print_from_args::<NftFactory>();
+}
+
+// ============================================
+// NFT FACTORY IMPLEMENTATION
+// ============================================
+
+#[public]
+impl NftFactory {
+    /// Sets the factory up for `owner` with an implementation contract address as it is deployed
+    #[constructor]
+    pub fn constructor(&mut self, owner: Address, implementation: Address) -> Result<(), Vec<u8>> {
+        if self.implementation.get() != Address::ZERO {
+            return Err(AlreadyInitialized {}.abi_encode());
+        }
+        
+        if implementation == Address::ZERO {
+            return Err(InvalidImplementation {}.abi_encode());
+        }
+        
+        self.ownable.initialize(owner)?;
+        self.implementation.set(implementation);
+        Ok(())
+    }
+
+    /// Sets the ERC-2771 trusted forwarder used by collections created afterwards (owner only)
+    pub fn set_trusted_forwarder(&mut self, forwarder: Address) -> Result<(), Vec<u8>> {
+        self.ownable.only_owner(self.vm().msg_sender())?;
+        self.trusted_forwarder.set(forwarder);
+        Ok(())
+    }
+
+    /// Returns the factory owner
+    pub fn owner(&self) -> Address {
+        self.ownable.owner()
+    }
+
+    /// Hands the factory over to `new_owner` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.ownable.transfer_ownership(caller, new_owner)
+    }
+
+    /// Stops collection creation (owner only)
+    pub fn pause(&mut self) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.ownable.only_owner(caller)?;
+        self.pausable.pause(caller)
+    }
+
+    /// Resumes collection creation (owner only)
+    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.ownable.only_owner(caller)?;
+        self.pausable.unpause(caller)
+    }
+
+    /// Returns true while the factory is paused
+    pub fn paused(&self) -> bool {
+        self.pausable.paused()
+    }
+
+    /// Sends ETH sent to the factory by mistake to `to` (owner only)
+    pub fn rescue_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        self.ownable.only_owner(self.vm().msg_sender())?;
+        self.rescuable.rescue_eth(to, amount)
+    }
+
+    /// Sends ERC20s sent to the factory by mistake to `to` (owner only)
+    pub fn rescue_erc20(&mut self, token: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        self.ownable.only_owner(self.vm().msg_sender())?;
+        self.rescuable.rescue_erc20(token, to, amount)
+    }
+
+    /// Returns the ERC-2771 trusted forwarder
+    pub fn trusted_forwarder(&self) -> Address {
+        self.trusted_forwarder.get()
+    }
+
+    /// Returns true if `forwarder` may relay calls on behalf of users (ERC-2771)
+    pub fn is_trusted_forwarder(&self, forwarder: Address) -> bool {
+        forwarder != Address::ZERO && forwarder == self.trusted_forwarder.get()
+    }
+
+    /// Runs several calls to the factory in one transaction, reverting all of them if one fails
+    /// Each call is delegated to the factory itself, so it acts for the batch's sender.
+    pub fn multicall(&mut self, calls: Vec<Bytes>) -> Result<Vec<Bytes>, Vec<u8>> {
+        let suffix = self._sender_suffix(self._msg_sender());
+
+        let mut results = Vec::with_capacity(calls.len());
+        for call in &calls {
+            results.push(Bytes(multicall::delegate_self(self, call, &suffix)?));
+        }
+        Ok(results)
+    }
+
+    /// Runs several calls like `multicall`, keeping the effects of those that succeed
+    /// Returns (success, return or revert data) for each call.
+    pub fn try_multicall(&mut self, calls: Vec<Bytes>) -> Vec<(bool, Bytes)> {
+        let suffix = self._sender_suffix(self._msg_sender());
+
+        calls
+            .iter()
+            .map(|call| match multicall::delegate_self(self, call, &suffix) {
+                Ok(data) => (true, Bytes(data)),
+                Err(data) => (false, Bytes(data)),
+            })
+            .collect()
+    }
+
+    /// Creates a new NFT collection for the caller
+    /// Each user can create their own collection with custom parameters
+    pub fn create_collection(
+        &mut self,
+        name: String,
+        symbol: String,
+        base_uri: String,
+    ) -> Result<Address, Vec<u8>> {
+        let creator = self._msg_sender();
+        self.pausable.when_not_paused()?;
+        let implementation = self.implementation.get();
+        
+        if implementation == Address::ZERO {
+            return Err(InvalidImplementation {}.abi_encode());
+        }
+
+        // Cloning and initializing call out to other contracts
+        self.reentrancy_guard.enter()?;
+        let result = self._create_collection(implementation, creator, name, symbol, base_uri);
+        self.reentrancy_guard.exit();
+        result
+    }
+
+    /// Returns the implementation contract address
+    pub fn get_implementation(&self) -> Address {
+        self.implementation.get()
+    }
+
+    /// Returns the total number of collections created
+    pub fn get_collection_count(&self) -> U256 {
+        self.collection_count.get()
+    }
+
+    /// Returns the collection address for a given collection ID
+    pub fn get_collection_by_id(&self, collection_id: U256) -> Address {
+        self.collections.get(collection_id)
+    }
+
+    /// Returns the collection ID for a given collection address
+    pub fn get_collection_id(&self, collection_address: Address) -> U256 {
+        self.collection_to_id.get(collection_address)
+    }
+
+    /// Returns all collections (paginated for gas efficiency)
+    pub fn get_collections(&self, start: U256, count: U256) -> Vec<Address> {
+        let mut collections = Vec::new();
+        let total = self.collection_count.get();
+        let end = if start + count > total { total } else { start + count };
+        
+        let mut i = start;
+        while i < end {
+            collections.push(self.collections.get(i));
+            i += U256::from(1);
+        }
+        
+        collections
+    }
+
+    // Internal function to deploy a minimal proxy (EIP-1167 clone)
+    fn _deploy_clone(&mut self, implementation: Address, salt: U256) -> Result<Address, Vec<u8>> {
+        // EIP-1167 minimal proxy bytecode
+        // This bytecode creates a proxy that delegates all calls to the implementation
+        let bytecode = clones::creation_code(implementation);
+
+        // Use CREATE2 for deterministic address
+        let salt_bytes = B256::from(salt.to_be_bytes::<32>());
+        
+        // Use vm() to deploy with CREATE2 (unsafe raw interface)
+        let result_addr: Address;
+        unsafe {
+            let bytecode_ptr = bytecode.as_ptr();
+            let bytecode_len = bytecode.len();
+            let value_ptr = U256::ZERO.as_le_slice().as_ptr();
+            let salt_ptr = salt_bytes.as_ptr();
+            let mut addr_out = [0u8; 20];
+            let mut addr_len = addr_out.len();
+            
+            self.vm().create2(
+                bytecode_ptr,
+                bytecode_len,
+                value_ptr,
+                salt_ptr,
+                addr_out.as_mut_ptr(),
+                &mut addr_len as *mut usize,
+            );
+            
+            result_addr = Address::from_slice(&addr_out);
+        }
+        
+        if result_addr == Address::ZERO {
+            return Err(DeploymentFailed {}.abi_encode());
+        }
+        
+        Ok(result_addr)
+    }
+
+    // Internal function to initialize a deployed collection
+    fn _initialize_collection(
+        &self,
+        collection_address: Address,
+        name: String,
+        symbol: String,
+        base_uri: String,
+        creator: Address,
+    ) -> Result<(), Vec<u8>> {
+        // Define the initialize function interface
+        sol! {
+            function initialize(string name, string symbol, string baseUri, address creator, address trustedForwarder);
+        }
+        
+        // Encode the initialize call with all parameters
+        let call_data = initializeCall {
+            name,
+            symbol,
+            baseUri: base_uri,
+            creator,
+            trustedForwarder: self.trusted_forwarder.get(),
+        }.abi_encode();
+        
+        let call = RawCall::new();
+        
+        unsafe {
+            match call.call(collection_address, &call_data) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(DeploymentFailed {}.abi_encode()),
+            }
+        }
+    }
+}
+
+// Internal helper functions
+impl NftFactory {
+    /// Deploys, initializes and registers a collection for `creator`
+    fn _create_collection(
+        &mut self,
+        implementation: Address,
+        creator: Address,
+        name: String,
+        symbol: String,
+        base_uri: String,
+    ) -> Result<Address, Vec<u8>> {
+        // Increment collection count
+        let collection_id = self.collection_count.get();
+        let new_collection_id = collection_id + U256::from(1);
+        self.collection_count.set(new_collection_id);
+
+        // Deploy new collection using CREATE2 for deterministic addresses
+        // This creates a minimal proxy (EIP-1167) that delegates to the implementation
+        let collection_address = self._deploy_clone(implementation, collection_id)?;
+        
+        // Initialize the newly deployed collection
+        self._initialize_collection(collection_address, name.clone(), symbol.clone(), base_uri.clone(), creator)?;
+        
+        // Store collection mapping
+        self.collections.setter(collection_id).set(collection_address);
+        // Note: creator_to_collections would need proper dynamic array handling in production
+        self.collection_to_id.setter(collection_address).set(collection_id);
+
+        // Emit event
+        log(self.vm(), CollectionCreated {
+            creator,
+            collection_address,
+            name,
+            symbol,
+            base_uri,
+            collection_id,
+        });
+
+        Ok(collection_address)
+    }
+
+    /// What to append to a call the factory makes to itself for `caller`: their address if relayed
+    fn _sender_suffix(&self, caller: Address) -> Vec<u8> {
+        if self.is_trusted_forwarder(self.vm().msg_sender()) { caller.to_vec() } else { Vec::new() }
+    }
+
+    /// Returns the account a call is made on behalf of (ERC-2771)
+    fn _msg_sender(&self) -> Address {
+        let sender = self.vm().msg_sender();
+        if !self.is_trusted_forwarder(sender) {
+            return sender;
+        }
+        erc2771::forwarded_sender().unwrap_or(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_common::pausable::EnforcedPause;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_owner_configures_factory() {
+        let vm = TestVM::default();
+        let mut factory = NftFactory::from(&vm);
+        let template = Address::from([7u8; 20]);
+        let forwarder = Address::from([8u8; 20]);
+
+        assert!(factory.constructor(vm.msg_sender(), Address::ZERO).is_err());
+        factory.constructor(vm.msg_sender(), template).unwrap();
+        assert_eq!(factory.owner(), vm.msg_sender());
+        assert_eq!(factory.get_implementation(), template);
+        assert_eq!(factory.constructor(vm.msg_sender(), template), Err(AlreadyInitialized {}.abi_encode()));
+
+        factory.set_trusted_forwarder(forwarder).unwrap();
+        assert!(factory.is_trusted_forwarder(forwarder));
+
+        factory.pause().unwrap();
+        assert_eq!(
+            factory.create_collection("Art".into(), "ART".into(), "ipfs://art/".into()),
+            Err(EnforcedPause {}.abi_encode())
+        );
+
+        let stranger = Address::from([9u8; 20]);
+        vm.set_sender(stranger);
+        assert_eq!(factory.unpause(), Err(Unauthorized { caller: stranger }.abi_encode()));
+    }
+}
@@ -0,0 +1,15 @@
+//! Events emitted by the NftFactory and the ERC721 collections it deploys
+//!
+//! Kept free of Stylus dependencies so off-chain tools (the registry indexer)
+//! can decode logs with exactly these definitions.
+
+use alloy_sol_types::sol;
+
+sol! {
+    event CollectionCreated(address indexed creator, address indexed collection_address, string name, string symbol, string base_uri, uint256 collection_id);
+    event ImplementationUpdated(address indexed old_implementation, address indexed new_implementation);
+    
+    event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
+    event Approval(address indexed owner, address indexed approved, uint256 indexed token_id);
+    event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+}
@@ -0,0 +1,214 @@
+//!
+//! Stylus standalone ERC20 template
+//!
+//! Deployed once; when a creator migrates a TokenFactory token out of the
+//! factory, the factory clones this template (EIP-1167), initializes the
+//! clone with the token's name, symbol, decimals and total supply, and
+//! holds that supply until each holder claims their balance 1:1.
+//!
+//! The program is ABI-equivalent with Solidity.
+//! To export the ABI, run `cargo stylus export-abi`.
+//!
+//! Note: this code is a template and has not been audited.
+//!
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[cfg(all(target_arch = "wasm32", feature = "export-abi"))]
+compile_error!("`export-abi` builds a native ABI printer, build the wasm without it");
+
+extern crate alloc;
+
+use alloc::{string::String, vec, vec::Vec};
+use stylus_common::errors::{require_recipient, require_sender};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256, U8},
+    alloy_sol_types::{sol, SolError},
+    prelude::*,
+};
+
+pub use stylus_common::errors::{AlreadyInitialized, InvalidRecipient, InvalidSender};
+
+// Define the ERC20 storage
+sol_storage! {
+    #[entrypoint]
+    pub struct StandaloneErc20 {
+        string name;
+        string symbol;
+        uint8 decimals;
+        uint256 total_supply;
+        bool initialized;
+
+        mapping(address => uint256) balances;
+        mapping(address => mapping(address => uint256)) allowances;
+    }
+}
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    error InsufficientBalance(address from, uint256 have, uint256 want);
+    error InsufficientAllowance(address owner, address spender, uint256 have, uint256 want);
+}
+
+// ============================================
+// STANDALONE ERC20 IMPLEMENTATION
+// ============================================
+
+#[public]
+impl StandaloneErc20 {
+    /// Initializes the token and mints `initial_supply` to `holder` (called by the factory)
+    pub fn initialize(
+        &mut self,
+        name: String,
+        symbol: String,
+        decimals: u8,
+        initial_supply: U256,
+        holder: Address,
+    ) -> Result<(), Vec<u8>> {
+        if self.initialized.get() {
+            return Err(AlreadyInitialized {}.abi_encode());
+        }
+        require_recipient(holder)?;
+
+        self.name.set_str(&name);
+        self.symbol.set_str(&symbol);
+        self.decimals.set(U8::from(decimals));
+        self.total_supply.set(initial_supply);
+        self.balances.setter(holder).set(initial_supply);
+        self.initialized.set(true);
+
+        log(self.vm(), Transfer { from: Address::ZERO, to: holder, value: initial_supply });
+        Ok(())
+    }
+
+    /// Returns the name of the token
+    pub fn name(&self) -> String {
+        self.name.get_string()
+    }
+
+    /// Returns the symbol of the token
+    pub fn symbol(&self) -> String {
+        self.symbol.get_string()
+    }
+
+    /// Returns the number of decimals
+    pub fn decimals(&self) -> u8 {
+        self.decimals.get().to::<u8>()
+    }
+
+    /// Returns the total supply
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    /// Returns the balance of an account
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    /// Returns the allowance of a spender for an owner
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.getter(owner).get(spender)
+    }
+
+    /// Transfers tokens from the caller to another account
+    pub fn transfer(&mut self, to: Address, amount: U256) -> Result<bool, Vec<u8>> {
+        let from = self.vm().msg_sender();
+        self._transfer(from, to, amount)?;
+        Ok(true)
+    }
+
+    /// Approves a spender to spend tokens on behalf of the caller
+    pub fn approve(&mut self, spender: Address, amount: U256) -> Result<bool, Vec<u8>> {
+        let owner = self.vm().msg_sender();
+        require_recipient(spender)?;
+
+        self.allowances.setter(owner).setter(spender).set(amount);
+        log(self.vm(), Approval { owner, spender, value: amount });
+        Ok(true)
+    }
+
+    /// Transfers tokens from one account to another using the caller's allowance
+    pub fn transfer_from(&mut self, from: Address, to: Address, amount: U256) -> Result<bool, Vec<u8>> {
+        let spender = self.vm().msg_sender();
+
+        let current_allowance = self.allowances.getter(from).get(spender);
+        if current_allowance < amount {
+            return Err(InsufficientAllowance {
+                owner: from,
+                spender,
+                have: current_allowance,
+                want: amount,
+            }.abi_encode());
+        }
+
+        // An unlimited allowance is never spent down
+        if current_allowance != U256::MAX {
+            self.allowances.setter(from).setter(spender).set(current_allowance - amount);
+        }
+
+        self._transfer(from, to, amount)?;
+        Ok(true)
+    }
+}
+
+// Internal helper functions
+impl StandaloneErc20 {
+    /// Moves `amount` from `from` to `to` and emits `Transfer`
+    fn _transfer(&mut self, from: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        require_sender(from)?;
+        require_recipient(to)?;
+
+        let from_balance = self.balances.get(from);
+        if from_balance < amount {
+            return Err(InsufficientBalance { from, have: from_balance, want: amount }.abi_encode());
+        }
+
+        self.balances.setter(from).set(from_balance - amount);
+        let to_balance = self.balances.get(to);
+        self.balances.setter(to).set(to_balance + amount);
+
+        log(self.vm(), Transfer { from, to, value: amount });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_initialize_and_transfer() {
+        let vm = TestVM::default();
+        let mut token = StandaloneErc20::from(&vm);
+        let factory = vm.msg_sender();
+        let alice = Address::from([2u8; 20]);
+        let bob = Address::from([3u8; 20]);
+
+        token.initialize("Migrated".into(), "MIG".into(), 6, U256::from(1_000), factory).unwrap();
+        assert_eq!(token.initialize("Again".into(), "AGN".into(), 18, U256::from(1), factory), Err(AlreadyInitialized {}.abi_encode()));
+        assert_eq!(token.name(), "Migrated");
+        assert_eq!(token.symbol(), "MIG");
+        assert_eq!(token.decimals(), 6);
+        assert_eq!(token.total_supply(), U256::from(1_000));
+        assert_eq!(token.balance_of(factory), U256::from(1_000));
+
+        // The factory hands out claims, holders trade freely from there
+        token.transfer(alice, U256::from(400)).unwrap();
+        vm.set_sender(alice);
+        token.approve(bob, U256::from(100)).unwrap();
+
+        vm.set_sender(bob);
+        token.transfer_from(alice, bob, U256::from(60)).unwrap();
+        assert_eq!(token.allowance(alice, bob), U256::from(40));
+        assert_eq!(token.balance_of(alice), U256::from(340));
+        assert_eq!(
+            token.transfer_from(alice, bob, U256::from(41)),
+            Err(InsufficientAllowance { owner: alice, spender: bob, have: U256::from(40), want: U256::from(41) }.abi_encode())
+        );
+    }
+}
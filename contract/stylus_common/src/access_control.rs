@@ -0,0 +1,140 @@
+//! Role-based access control
+//!
+//! Roles are `bytes32` identifiers, conventionally `keccak256("ROLE_NAME")`.
+//! Each role has an admin role whose members may grant and revoke it;
+//! `DEFAULT_ADMIN_ROLE` administers every role unless told otherwise.
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::{Address, B256};
+use alloy_sol_types::{sol, SolError};
+use stylus_sdk::prelude::*;
+
+/// Admin of every role by default, and of itself
+pub const DEFAULT_ADMIN_ROLE: B256 = B256::ZERO;
+
+sol_storage! {
+    pub struct RoleData {
+        mapping(address => bool) members;
+        bytes32 admin_role;
+    }
+
+    pub struct AccessControl {
+        mapping(bytes32 => RoleData) roles;
+    }
+}
+
+sol! {
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleAdminChanged(bytes32 indexed role, bytes32 indexed previous_admin_role, bytes32 indexed new_admin_role);
+
+    error MissingRole(address account, bytes32 role);
+    error BadConfirmation();
+}
+
+impl AccessControl {
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.roles.getter(role).members.get(account)
+    }
+
+    /// Returns the role whose members administer `role`
+    pub fn get_role_admin(&self, role: B256) -> B256 {
+        self.roles.getter(role).admin_role.get()
+    }
+
+    /// Fails unless `account` holds `role`
+    pub fn only_role(&self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        if !self.has_role(role, account) {
+            return Err(MissingRole { account, role }.abi_encode());
+        }
+        Ok(())
+    }
+
+    /// Grants `role` to `account` (admins of `role` only)
+    pub fn grant_role(&mut self, caller: Address, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        self.only_role(self.get_role_admin(role), caller)?;
+        self._grant_role(role, account, caller);
+        Ok(())
+    }
+
+    /// Revokes `role` from `account` (admins of `role` only)
+    pub fn revoke_role(&mut self, caller: Address, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        self.only_role(self.get_role_admin(role), caller)?;
+        self._revoke_role(role, account, caller);
+        Ok(())
+    }
+
+    /// Gives up `role`; `account` must be the caller, guarding against typos
+    pub fn renounce_role(&mut self, caller: Address, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        if account != caller {
+            return Err(BadConfirmation {}.abi_encode());
+        }
+        self._revoke_role(role, account, caller);
+        Ok(())
+    }
+
+    /// Grants `role` without checking `sender`, returns false if it was already held
+    pub fn _grant_role(&mut self, role: B256, account: Address, sender: Address) -> bool {
+        if self.has_role(role, account) {
+            return false;
+        }
+        self.roles.setter(role).members.setter(account).set(true);
+        log(self.vm(), RoleGranted { role, account, sender });
+        true
+    }
+
+    /// Revokes `role` without checking `sender`, returns false if it was not held
+    pub fn _revoke_role(&mut self, role: B256, account: Address, sender: Address) -> bool {
+        if !self.has_role(role, account) {
+            return false;
+        }
+        self.roles.setter(role).members.setter(account).set(false);
+        log(self.vm(), RoleRevoked { role, account, sender });
+        true
+    }
+
+    /// Makes `admin_role` the admin of `role`
+    pub fn _set_role_admin(&mut self, role: B256, admin_role: B256) {
+        let previous_admin_role = self.get_role_admin(role);
+        self.roles.setter(role).admin_role.set(admin_role);
+        log(self.vm(), RoleAdminChanged { role, previous_admin_role, new_admin_role: admin_role });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_access_control() {
+        let vm = TestVM::default();
+        let mut access = AccessControl::from(&vm);
+        let admin = Address::from([1u8; 20]);
+        let alice = Address::from([2u8; 20]);
+        let minter = B256::from([7u8; 32]);
+        let minter_admin = B256::from([8u8; 32]);
+
+        assert!(access._grant_role(DEFAULT_ADMIN_ROLE, admin, admin));
+        assert!(!access._grant_role(DEFAULT_ADMIN_ROLE, admin, admin));
+
+        // Only admins of a role may grant it
+        assert_eq!(
+            access.grant_role(alice, minter, alice),
+            Err(MissingRole { account: alice, role: DEFAULT_ADMIN_ROLE }.abi_encode())
+        );
+        access.grant_role(admin, minter, alice).unwrap();
+        assert!(access.has_role(minter, alice));
+        assert!(access.only_role(minter, alice).is_ok());
+        assert!(access.only_role(minter, admin).is_err());
+
+        // Handing the role to another admin role locks out the default admin
+        access._set_role_admin(minter, minter_admin);
+        assert_eq!(access.get_role_admin(minter), minter_admin);
+        assert!(access.revoke_role(admin, minter, alice).is_err());
+
+        assert_eq!(access.renounce_role(admin, minter, alice), Err(BadConfirmation {}.abi_encode()));
+        access.renounce_role(alice, minter, alice).unwrap();
+        assert!(!access.has_role(minter, alice));
+    }
+}
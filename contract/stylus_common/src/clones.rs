@@ -0,0 +1,33 @@
+//! EIP-1167 minimal proxies
+//!
+//! A clone forwards every call to its implementation with DELEGATECALL, so
+//! a contract deployed once can back any number of cheap instances.
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::Address;
+
+/// Creation code of a minimal proxy delegating to `implementation`
+///
+/// Deploy it with CREATE2 for a deterministic clone address.
+pub fn creation_code(implementation: Address) -> Vec<u8> {
+    let mut bytecode = vec![0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+    bytecode.extend_from_slice(implementation.as_slice());
+    bytecode.extend_from_slice(&[
+        0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+    ]);
+    bytecode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creation_code_embeds_implementation() {
+        let implementation = Address::from([7u8; 20]);
+        let code = creation_code(implementation);
+
+        assert_eq!(code.len(), 45);
+        assert_eq!(&code[10..30], implementation.as_slice());
+    }
+}
@@ -0,0 +1,160 @@
+//! EIP-712 typed structured data hashing, signature recovery and replay nonces
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::{address, uint, Address, B256, U256};
+use alloy_sol_types::{sol, SolError};
+use stylus_sdk::{crypto::keccak, prelude::*};
+
+/// Type string of a domain with name, version, chain id and verifying contract
+pub const DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+/// The ecrecover precompile
+pub const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+/// Largest `s` of a canonical signature, half the order of secp256k1
+const SECP256K1N_HALF: U256 = uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+
+sol_storage! {
+    /// Per-account counters making every signed message usable once
+    pub struct Nonces {
+        mapping(address => uint256) nonces;
+    }
+}
+
+sol! {
+    error InvalidNonce(address owner, uint256 expected, uint256 got);
+}
+
+impl Nonces {
+    /// Returns the next nonce expected from `owner`
+    pub fn nonce(&self, owner: Address) -> U256 {
+        self.nonces.get(owner)
+    }
+
+    /// Consumes `nonce` if it is the next one expected from `owner`
+    pub fn use_nonce(&mut self, owner: Address, nonce: U256) -> Result<(), Vec<u8>> {
+        let expected = self.nonces.get(owner);
+        if expected != nonce {
+            return Err(InvalidNonce { owner, expected, got: nonce }.abi_encode());
+        }
+        self.nonces.setter(owner).set(expected + U256::from(1));
+        Ok(())
+    }
+}
+
+/// Returns the separator of the domain (name, version, chain_id, verifying_contract)
+pub fn domain_separator(name: &[u8], version: &[u8], chain_id: u64, verifying_contract: Address) -> B256 {
+    let mut encoded = Vec::with_capacity(5 * 32);
+    encoded.extend_from_slice(keccak(DOMAIN_TYPE).as_slice());
+    encoded.extend_from_slice(keccak(name).as_slice());
+    encoded.extend_from_slice(keccak(version).as_slice());
+    encoded.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+    encoded.extend_from_slice(verifying_contract.into_word().as_slice());
+    keccak(&encoded)
+}
+
+/// Returns the digest to sign for a struct hash within a domain
+pub fn typed_data_hash(domain_separator: B256, struct_hash: B256) -> B256 {
+    let mut message = [0u8; 2 + 2 * 32];
+    message[..2].copy_from_slice(&[0x19, 0x01]);
+    message[2..34].copy_from_slice(domain_separator.as_slice());
+    message[34..].copy_from_slice(struct_hash.as_slice());
+    keccak(message)
+}
+
+/// Recovers the signer of `digest` from a 65-byte (r, s, v) signature
+/// High-s signatures are rejected, so no signature has a second valid form.
+pub fn recover<S: TopLevelStorage + HostAccess>(storage: &S, digest: B256, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 || U256::from_be_slice(&signature[32..64]) > SECP256K1N_HALF {
+        return None;
+    }
+
+    let v = match signature[64] {
+        0 | 1 => signature[64] + 27,
+        v => v,
+    };
+
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(digest.as_slice());
+    input[63] = v;
+    input[64..128].copy_from_slice(&signature[..64]);
+
+    let output = storage.vm().static_call(&storage, ECRECOVER, &input).ok()?;
+    if output.len() != 32 {
+        return None;
+    }
+
+    let signer = Address::from_slice(&output[12..32]);
+    (signer != Address::ZERO).then_some(signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    sol_storage! {
+        pub struct Verifier {
+            Nonces nonces;
+        }
+    }
+
+    unsafe impl TopLevelStorage for Verifier {}
+
+    #[test]
+    fn test_nonces() {
+        let vm = TestVM::default();
+        let mut nonces = Nonces::from(&vm);
+        let owner = Address::from([1u8; 20]);
+
+        assert_eq!(nonces.nonce(owner), U256::ZERO);
+        assert_eq!(
+            nonces.use_nonce(owner, U256::from(1)),
+            Err(InvalidNonce { owner, expected: U256::ZERO, got: U256::from(1) }.abi_encode())
+        );
+        nonces.use_nonce(owner, U256::ZERO).unwrap();
+        assert_eq!(nonces.nonce(owner), U256::from(1));
+        assert!(nonces.use_nonce(owner, U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_hashing() {
+        let contract = Address::from([9u8; 20]);
+        let separator = domain_separator(b"MinimalForwarder", b"0.0.1", 412346, contract);
+
+        assert_ne!(separator, domain_separator(b"MinimalForwarder", b"0.0.1", 1, contract));
+        assert_ne!(separator, domain_separator(b"MinimalForwarder", b"0.0.2", 412346, contract));
+
+        let struct_hash = keccak(b"request");
+        let mut message = vec![0x19, 0x01];
+        message.extend_from_slice(separator.as_slice());
+        message.extend_from_slice(struct_hash.as_slice());
+        assert_eq!(typed_data_hash(separator, struct_hash), keccak(&message));
+    }
+
+    #[test]
+    fn test_recover_rejects_high_s() {
+        let vm = TestVM::default();
+        let verifier = Verifier::from(&vm);
+        let digest = keccak(b"request");
+        let signer = Address::from([7u8; 20]);
+        // s and n - s both recover to the signer, with opposite parities
+        let order = SECP256K1N_HALF * U256::from(2) + U256::from(1);
+        let s = U256::from(12345);
+
+        let sign = |s: U256, v: u8| [[1u8; 32].as_slice(), &s.to_be_bytes::<32>(), &[v]].concat();
+        let (low, high) = (sign(s, 27), sign(order - s, 28));
+        for signature in [&low, &high] {
+            let mut input = [0u8; 128];
+            input[..32].copy_from_slice(digest.as_slice());
+            input[63] = signature[64];
+            input[64..].copy_from_slice(&signature[..64]);
+            vm.mock_static_call(ECRECOVER, input.to_vec(), Ok(signer.into_word().to_vec()));
+        }
+
+        assert_eq!(recover(&verifier, digest, &low), Some(signer));
+        assert_eq!(recover(&verifier, digest, &high), None);
+        assert_eq!(recover(&verifier, digest, &low[..64]), None);
+    }
+}
@@ -0,0 +1,77 @@
+//! ERC-2771 calls relayed by a trusted forwarder
+//!
+//! The forwarder appends the signer's address to the calldata. The router
+//! `#[public]` generates decodes arguments strictly and would reject those
+//! 20 bytes, so contracts accepting relayed calls route through `entrypoint`
+//! instead of a struct `#[entrypoint]`: it takes the sender off the end of
+//! the calldata before routing, and `forwarded_sender` hands it to the call.
+//! `msg_data_hash` hashes the calldata without it, like `_msgData()` does in
+//! Solidity.
+//!
+//! Every call frame runs in its own instance of the program, so what is kept
+//! here only ever belongs to the call being routed.
+
+use alloc::vec::Vec;
+use core::{borrow::BorrowMut, cell::Cell};
+use stylus_sdk::{
+    abi::{router_entrypoint, Router},
+    alloy_primitives::{Address, B256, U256},
+    crypto::keccak,
+    host::VM,
+    prelude::*,
+    ArbResult,
+};
+
+/// Length of the sender a forwarder appends
+const SENDER_LEN: usize = 20;
+
+/// The call being routed
+struct Frame {
+    sender: Cell<Option<Address>>,
+    data: Cell<Vec<u8>>,
+}
+
+// Programs are single-threaded
+unsafe impl Sync for Frame {}
+
+static FRAME: Frame = Frame { sender: Cell::new(None), data: Cell::new(Vec::new()) };
+
+/// Routes a call to `S`, stripping the sender a trusted forwarder appended
+///
+/// `is_trusted_forwarder` tells whether the caller is the contract's forwarder.
+pub fn entrypoint<S>(mut input: Vec<u8>, host: VM, is_trusted_forwarder: impl FnOnce(&S, Address) -> bool) -> ArbResult
+where
+    S: Router<S> + StorageType + TopLevelStorage + BorrowMut<<S as Router<S>>::Storage> + ValueDenier + HostAccess,
+{
+    let storage = unsafe { S::new(U256::ZERO, 0, host.clone()) };
+    // Selector and sender at least, anything shorter is not a relayed call
+    let sender = if input.len() >= 4 + SENDER_LEN && is_trusted_forwarder(&storage, storage.vm().msg_sender()) {
+        let sender = Address::from_slice(&input[input.len() - SENDER_LEN..]);
+        input.truncate(input.len() - SENDER_LEN);
+        Some(sender)
+    } else {
+        None
+    };
+
+    let previous_sender = FRAME.sender.replace(sender);
+    let previous_data = FRAME.data.replace(input.clone());
+    let result = router_entrypoint::<S, S>(input, host);
+    FRAME.sender.set(previous_sender);
+    FRAME.data.set(previous_data);
+    result
+}
+
+/// The sender a trusted forwarder relayed the current call for, if it was relayed
+///
+/// Only meaningful while `msg_sender` is the trusted forwarder.
+pub fn forwarded_sender() -> Option<Address> {
+    FRAME.sender.get()
+}
+
+/// Hash of the current call's calldata, without the sender a trusted forwarder appended
+pub fn msg_data_hash() -> B256 {
+    let data = FRAME.data.take();
+    let hash = keccak(&data);
+    FRAME.data.set(data);
+    hash
+}
@@ -0,0 +1,43 @@
+//! Errors and checks shared by every contract
+
+use alloc::vec::Vec;
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolError};
+
+sol! {
+    error Unauthorized(address caller);
+    error AlreadyInitialized();
+    error InvalidSender(address from);
+    error InvalidRecipient(address to);
+}
+
+/// Rejects the zero address as the source of a transfer or approval
+pub fn require_sender(from: Address) -> Result<(), Vec<u8>> {
+    if from == Address::ZERO {
+        return Err(InvalidSender { from }.abi_encode());
+    }
+    Ok(())
+}
+
+/// Rejects the zero address as the destination of a transfer or approval
+pub fn require_recipient(to: Address) -> Result<(), Vec<u8>> {
+    if to == Address::ZERO {
+        return Err(InvalidRecipient { to }.abi_encode());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_address_checks() {
+        let account = Address::from([1u8; 20]);
+
+        assert!(require_sender(account).is_ok());
+        assert!(require_recipient(account).is_ok());
+        assert_eq!(require_sender(Address::ZERO), Err(InvalidSender { from: Address::ZERO }.abi_encode()));
+        assert_eq!(require_recipient(Address::ZERO), Err(InvalidRecipient { to: Address::ZERO }.abi_encode()));
+    }
+}
@@ -0,0 +1,43 @@
+//!
+//! Reusable building blocks for the Stylus contracts of this repository
+//!
+//! Every module is a storage struct meant to be embedded as a field of a
+//! contract, for example:
+//!
+//! ```ignore
+//! sol_storage! {
+//!     #[entrypoint]
+//!     pub struct MyContract {
+//!         Ownable ownable;
+//!         Pausable pausable;
+//!     }
+//! }
+//! ```
+//!
+//! The structs expose plain methods rather than `#[public]` ones: the embedding
+//! contract decides which of them to export and passes in the account a call
+//! is made on behalf of (which differs from `msg_sender` under ERC-2771).
+//!
+//! Note: this code is a template and has not been audited.
+//!
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod access_control;
+pub mod clones;
+pub mod eip712;
+pub mod erc2771;
+pub mod errors;
+pub mod multicall;
+pub mod ownable;
+pub mod pausable;
+pub mod reentrancy_guard;
+pub mod rescuable;
+
+pub use access_control::AccessControl;
+pub use eip712::Nonces;
+pub use ownable::Ownable;
+pub use pausable::Pausable;
+pub use reentrancy_guard::ReentrancyGuard;
+pub use rescuable::Rescuable;
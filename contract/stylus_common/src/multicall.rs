@@ -0,0 +1,23 @@
+//! Batching calls to a contract through DELEGATECALL to itself
+//!
+//! Each call runs in the contract's own context with the batch's
+//! `msg_sender`, so state-changing calls act for the original caller.
+//! Re-entering the program this way needs stylus-sdk's `reentrant` feature.
+//!
+//! Under ERC-2771 the forwarded sender is appended to every call, exactly
+//! as the forwarder appended it to the batch.
+
+use alloc::vec::Vec;
+use stylus_sdk::prelude::*;
+
+/// DELEGATECALLs the contract itself with `call`, followed by `suffix` (the forwarded sender, or nothing)
+///
+/// Returns the call's return data, or its revert data if it failed.
+pub fn delegate_self<S: TopLevelStorage + HostAccess>(storage: &mut S, call: &[u8], suffix: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    let mut calldata = Vec::with_capacity(call.len() + suffix.len());
+    calldata.extend_from_slice(call);
+    calldata.extend_from_slice(suffix);
+    let this = storage.vm().contract_address();
+    unsafe { storage.vm().delegate_call(&storage, this, &calldata) }.map_err(Vec::from)
+}
+
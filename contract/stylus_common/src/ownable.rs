@@ -0,0 +1,97 @@
+//! Single-owner access control
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolError};
+use stylus_sdk::prelude::*;
+
+use crate::errors::{AlreadyInitialized, Unauthorized};
+
+sol_storage! {
+    pub struct Ownable {
+        address owner;
+    }
+}
+
+sol! {
+    event OwnershipTransferred(address indexed previous_owner, address indexed new_owner);
+
+    error InvalidOwner(address owner);
+}
+
+impl Ownable {
+    /// Returns the current owner, zero until initialized
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Sets the first owner, once
+    pub fn initialize(&mut self, owner: Address) -> Result<(), Vec<u8>> {
+        if self.owner.get() != Address::ZERO {
+            return Err(AlreadyInitialized {}.abi_encode());
+        }
+        if owner == Address::ZERO {
+            return Err(InvalidOwner { owner }.abi_encode());
+        }
+
+        self._transfer_ownership(owner);
+        Ok(())
+    }
+
+    /// Fails unless `account` is the owner
+    pub fn only_owner(&self, account: Address) -> Result<(), Vec<u8>> {
+        if account == Address::ZERO || account != self.owner.get() {
+            return Err(Unauthorized { caller: account }.abi_encode());
+        }
+        Ok(())
+    }
+
+    /// Hands ownership over to `new_owner` (owner only)
+    ///
+    /// There is no renouncing: an ownerless contract could be initialized again.
+    pub fn transfer_ownership(&mut self, caller: Address, new_owner: Address) -> Result<(), Vec<u8>> {
+        self.only_owner(caller)?;
+        if new_owner == Address::ZERO {
+            return Err(InvalidOwner { owner: new_owner }.abi_encode());
+        }
+
+        self._transfer_ownership(new_owner);
+        Ok(())
+    }
+
+    fn _transfer_ownership(&mut self, new_owner: Address) {
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        log(self.vm(), OwnershipTransferred { previous_owner, new_owner });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_ownable() {
+        let vm = TestVM::default();
+        let mut ownable = Ownable::from(&vm);
+        let owner = Address::from([1u8; 20]);
+        let other = Address::from([2u8; 20]);
+
+        assert_eq!(ownable.owner(), Address::ZERO);
+        assert!(ownable.only_owner(Address::ZERO).is_err());
+        assert!(ownable.initialize(Address::ZERO).is_err());
+
+        ownable.initialize(owner).unwrap();
+        assert_eq!(ownable.owner(), owner);
+        assert!(ownable.only_owner(owner).is_ok());
+        assert_eq!(ownable.only_owner(other), Err(Unauthorized { caller: other }.abi_encode()));
+        assert_eq!(ownable.initialize(other), Err(AlreadyInitialized {}.abi_encode()));
+
+        assert!(ownable.transfer_ownership(other, other).is_err());
+        assert!(ownable.transfer_ownership(owner, Address::ZERO).is_err());
+        ownable.transfer_ownership(owner, other).unwrap();
+        assert_eq!(ownable.owner(), other);
+        assert!(ownable.only_owner(owner).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Emergency stop for state-changing entrypoints
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolError};
+use stylus_sdk::prelude::*;
+
+sol_storage! {
+    pub struct Pausable {
+        bool paused;
+    }
+}
+
+sol! {
+    event Paused(address account);
+    event Unpaused(address account);
+
+    error EnforcedPause();
+    error ExpectedPause();
+}
+
+impl Pausable {
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Fails while paused
+    pub fn when_not_paused(&self) -> Result<(), Vec<u8>> {
+        if self.paused.get() {
+            return Err(EnforcedPause {}.abi_encode());
+        }
+        Ok(())
+    }
+
+    /// Fails unless paused
+    pub fn when_paused(&self) -> Result<(), Vec<u8>> {
+        if !self.paused.get() {
+            return Err(ExpectedPause {}.abi_encode());
+        }
+        Ok(())
+    }
+
+    /// Pauses on behalf of `account`; callers check `account` may do so
+    pub fn pause(&mut self, account: Address) -> Result<(), Vec<u8>> {
+        self.when_not_paused()?;
+        self.paused.set(true);
+        log(self.vm(), Paused { account });
+        Ok(())
+    }
+
+    /// Unpauses on behalf of `account`; callers check `account` may do so
+    pub fn unpause(&mut self, account: Address) -> Result<(), Vec<u8>> {
+        self.when_paused()?;
+        self.paused.set(false);
+        log(self.vm(), Unpaused { account });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_pausable() {
+        let vm = TestVM::default();
+        let mut pausable = Pausable::from(&vm);
+        let account = Address::from([1u8; 20]);
+
+        assert!(!pausable.paused());
+        assert!(pausable.when_not_paused().is_ok());
+        assert_eq!(pausable.unpause(account), Err(ExpectedPause {}.abi_encode()));
+
+        pausable.pause(account).unwrap();
+        assert!(pausable.paused());
+        assert_eq!(pausable.when_not_paused(), Err(EnforcedPause {}.abi_encode()));
+        assert!(pausable.when_paused().is_ok());
+        assert!(pausable.pause(account).is_err());
+
+        pausable.unpause(account).unwrap();
+        assert!(!pausable.paused());
+    }
+}
@@ -0,0 +1,61 @@
+//! Guard against reentering a function while it makes external calls
+//!
+//! Stylus programs are non-reentrant unless built with the `reentrant`
+//! feature, which the factories enable for `multicall`; the guard keeps
+//! functions making external calls safe under it.
+
+use alloc::{vec, vec::Vec};
+use alloy_sol_types::{sol, SolError};
+use stylus_sdk::prelude::*;
+
+sol_storage! {
+    pub struct ReentrancyGuard {
+        bool entered;
+    }
+}
+
+sol! {
+    error ReentrantCall();
+}
+
+impl ReentrancyGuard {
+    /// Marks the guarded section as entered, failing if it already is
+    ///
+    /// Pair with `exit` on every path, including errors.
+    pub fn enter(&mut self) -> Result<(), Vec<u8>> {
+        if self.entered.get() {
+            return Err(ReentrantCall {}.abi_encode());
+        }
+        self.entered.set(true);
+        Ok(())
+    }
+
+    /// Leaves the guarded section
+    pub fn exit(&mut self) {
+        self.entered.set(false);
+    }
+
+    pub fn entered(&self) -> bool {
+        self.entered.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_reentrancy_guard() {
+        let vm = TestVM::default();
+        let mut guard = ReentrancyGuard::from(&vm);
+
+        guard.enter().unwrap();
+        assert!(guard.entered());
+        assert_eq!(guard.enter(), Err(ReentrantCall {}.abi_encode()));
+
+        guard.exit();
+        assert!(!guard.entered());
+        assert!(guard.enter().is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+//! Recovery of ETH and ERC20s sent to a contract by mistake
+//!
+//! Whatever the contract holds on its users' behalf is recorded as reserved
+//! and can never be rescued; only the surplus above the reserves can.
+
+use alloc::{vec, vec::Vec};
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, SolCall, SolError};
+use stylus_sdk::{call::RawCall, prelude::*};
+
+use crate::errors::require_recipient;
+
+sol_storage! {
+    pub struct Rescuable {
+        uint256 reserved_eth;
+        mapping(address => uint256) reserved_erc20;
+    }
+}
+
+sol! {
+    event EthRescued(address indexed to, uint256 amount);
+    event Erc20Rescued(address indexed token, address indexed to, uint256 amount);
+
+    error RescueExceedsSurplus(address token, uint256 available, uint256 requested);
+    error RescueFailed(address token);
+
+    function balanceOf(address account) external view returns (uint256);
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+
+impl Rescuable {
+    /// ETH held on users' behalf
+    pub fn reserved_eth(&self) -> U256 {
+        self.reserved_eth.get()
+    }
+
+    /// Amount of `token` held on users' behalf
+    pub fn reserved_erc20(&self, token: Address) -> U256 {
+        self.reserved_erc20.get(token)
+    }
+
+    /// Records ETH now held on users' behalf
+    pub fn reserve_eth(&mut self, amount: U256) {
+        let reserved = self.reserved_eth.get();
+        self.reserved_eth.set(reserved + amount);
+    }
+
+    /// Releases ETH no longer held on users' behalf (paid out or burned)
+    pub fn release_eth(&mut self, amount: U256) {
+        let reserved = self.reserved_eth.get();
+        self.reserved_eth.set(reserved.saturating_sub(amount));
+    }
+
+    /// Records `token`s now held on users' behalf
+    pub fn reserve_erc20(&mut self, token: Address, amount: U256) {
+        let reserved = self.reserved_erc20.get(token);
+        self.reserved_erc20.setter(token).set(reserved + amount);
+    }
+
+    /// Releases `token`s no longer held on users' behalf
+    pub fn release_erc20(&mut self, token: Address, amount: U256) {
+        let reserved = self.reserved_erc20.get(token);
+        self.reserved_erc20.setter(token).set(reserved.saturating_sub(amount));
+    }
+
+    /// Sends unreserved ETH to `to`; callers check who may rescue
+    pub fn rescue_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        require_recipient(to)?;
+
+        let balance = self.vm().balance(self.vm().contract_address());
+        let available = balance.saturating_sub(self.reserved_eth.get());
+        if amount > available {
+            return Err(RescueExceedsSurplus { token: Address::ZERO, available, requested: amount }.abi_encode());
+        }
+
+        if unsafe { RawCall::new_with_value(amount).call(to, &[]) }.is_err() {
+            return Err(RescueFailed { token: Address::ZERO }.abi_encode());
+        }
+
+        log(self.vm(), EthRescued { to, amount });
+        Ok(())
+    }
+
+    /// Sends unreserved `token`s to `to`; callers check who may rescue
+    pub fn rescue_erc20(&mut self, token: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        require_recipient(to)?;
+
+        let account = self.vm().contract_address();
+        if token == account {
+            return Err(RescueFailed { token }.abi_encode());
+        }
+        let balance = unsafe { RawCall::new_static().call(token, &balanceOfCall { account }.abi_encode()) }
+            .ok()
+            .and_then(|output| balanceOfCall::abi_decode_returns(&output, true).ok())
+            .ok_or_else(|| RescueFailed { token }.abi_encode())?
+            ._0;
+
+        let available = balance.saturating_sub(self.reserved_erc20.get(token));
+        if amount > available {
+            return Err(RescueExceedsSurplus { token, available, requested: amount }.abi_encode());
+        }
+
+        // Tokens that return nothing from `transfer` are accepted, like SafeERC20 does
+        let output = unsafe { RawCall::new().call(token, &transferCall { to, amount }.abi_encode()) }
+            .map_err(|_| RescueFailed { token }.abi_encode())?;
+        let returned_true = matches!(transferCall::abi_decode_returns(&output, true), Ok(ret) if ret._0);
+        if !output.is_empty() && !returned_true {
+            return Err(RescueFailed { token }.abi_encode());
+        }
+
+        log(self.vm(), Erc20Rescued { token, to, amount });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_reserves() {
+        let vm = TestVM::default();
+        let mut rescuable = Rescuable::from(&vm);
+        let token = Address::from([1u8; 20]);
+
+        rescuable.reserve_eth(U256::from(100));
+        rescuable.release_eth(U256::from(30));
+        assert_eq!(rescuable.reserved_eth(), U256::from(70));
+        rescuable.release_eth(U256::from(1_000));
+        assert_eq!(rescuable.reserved_eth(), U256::ZERO);
+
+        rescuable.reserve_erc20(token, U256::from(5));
+        assert_eq!(rescuable.reserved_erc20(token), U256::from(5));
+        assert_eq!(rescuable.reserved_erc20(Address::ZERO), U256::ZERO);
+
+        // Nothing held beyond the reserves, so nothing can be rescued
+        rescuable.reserve_eth(U256::from(1));
+        assert_eq!(
+            rescuable.rescue_eth(Address::from([2u8; 20]), U256::from(1)),
+            Err(RescueExceedsSurplus { token: Address::ZERO, available: U256::ZERO, requested: U256::from(1) }.abi_encode())
+        );
+        assert!(rescuable.rescue_eth(Address::ZERO, U256::ZERO).is_err());
+    }
+}
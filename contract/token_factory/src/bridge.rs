@@ -0,0 +1,63 @@
+//! Moving factory tokens between this Arbitrum chain and L1
+//!
+//! Withdrawals burn the L2 balance and send an L2→L1 message through the
+//! ArbSys precompile; once the challenge period is over it is executed on L1
+//! against the gateway set by the factory owner. Deposits come back as
+//! retryable tickets from that gateway, whose sender L2 sees aliased, and can
+//! only bring back what was withdrawn.
+
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{address, Address, U160, U256},
+    alloy_sol_types::{sol, SolCall},
+    prelude::*,
+};
+
+/// The ArbSys precompile
+pub const ARB_SYS: Address = address!("0000000000000000000000000000000000000064");
+
+/// Added to an L1 contract's address when it sends a message to L2
+pub const L1_TO_L2_ALIAS_OFFSET: Address = address!("1111000000000000000000000000000000001111");
+
+sol! {
+    error BridgeNotConfigured();
+    error NotL1Gateway(address caller);
+    error L2ToL1MessageFailed();
+    error InboundExceedsBridged(uint256 token_id, uint256 amount, uint256 bridged_out);
+
+    /// ArbSys: sends an L2→L1 message, returning its unique id
+    function sendTxToL1(address destination, bytes data) external payable returns (uint256);
+
+    /// Executed on the L1 gateway once the withdrawal is confirmed
+    function finalizeWithdrawal(uint256 token_id, address from, address to, uint256 amount) external;
+}
+
+/// The address an L1 contract sends L2 messages from
+pub fn apply_l1_to_l2_alias(l1_address: Address) -> Address {
+    let aliased = U160::from_be_bytes(l1_address.0 .0).wrapping_add(U160::from_be_bytes(L1_TO_L2_ALIAS_OFFSET.0 .0));
+    Address::from(aliased.to_be_bytes::<20>())
+}
+
+/// The ArbSys call sending a withdrawal to `gateway`
+pub fn withdrawal_message(gateway: Address, token_id: U256, from: Address, to: Address, amount: U256) -> Vec<u8> {
+    let data = finalizeWithdrawalCall { token_id, from, to, amount }.abi_encode();
+    sendTxToL1Call { destination: gateway, data: data.into() }.abi_encode()
+}
+
+/// Sends an L2→L1 message through ArbSys on behalf of `storage`'s contract, returning its id
+pub fn send_to_l1<S: TopLevelStorage + HostAccess>(storage: &mut S, message: &[u8]) -> Option<U256> {
+    let returned = storage.vm().call(&storage, ARB_SYS, message).ok()?;
+    sendTxToL1Call::abi_decode_returns(&returned, true).ok().map(|ret| ret._0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_wraps_around() {
+        let l1 = address!("ffffffffffffffffffffffffffffffffffffffff");
+        assert_eq!(apply_l1_to_l2_alias(l1), address!("1111000000000000000000000000000000001110"));
+        assert_eq!(apply_l1_to_l2_alias(Address::ZERO), L1_TO_L2_ALIAS_OFFSET);
+    }
+}
@@ -0,0 +1,104 @@
+//! Transfer-compliance rules a creator can attach to their token
+//!
+//! Anti-whale limits for launches: a maximum balance per wallet, a maximum
+//! amount per transfer and a cooldown between two transfers from the same
+//! address. Zero disables a limit. Exempt addresses (and the creator, who
+//! distributes the supply) are not limited, and once locked the rules can
+//! never change again.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    alloy_sol_types::{sol, SolError},
+    prelude::*,
+};
+
+sol_storage! {
+    pub struct ComplianceRules {
+        uint256 max_wallet;
+        uint256 max_transfer;
+        uint256 cooldown;  // Seconds between two transfers from one address
+        bool locked;
+        mapping(address => bool) exempt;
+        mapping(address => uint256) last_transfer_at;
+    }
+}
+
+sol! {
+    error MaxWalletExceeded(uint256 token_id, address account, uint256 balance, uint256 max_wallet);
+    error MaxTransferExceeded(uint256 token_id, uint256 amount, uint256 max_transfer);
+    error TransferCooldown(uint256 token_id, address account, uint256 available_at);
+    error RulesLocked(uint256 token_id);
+}
+
+impl ComplianceRules {
+    /// Returns (max_wallet, max_transfer, cooldown, locked)
+    pub fn get(&self) -> (U256, U256, U256, bool) {
+        (self.max_wallet.get(), self.max_transfer.get(), self.cooldown.get(), self.locked.get())
+    }
+
+    pub fn is_exempt(&self, account: Address) -> bool {
+        self.exempt.get(account)
+    }
+
+    /// Fails once the rules are locked
+    pub fn when_unlocked(&self, token_id: U256) -> Result<(), Vec<u8>> {
+        if self.locked.get() {
+            return Err(RulesLocked { token_id }.abi_encode());
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, max_wallet: U256, max_transfer: U256, cooldown: U256) {
+        self.max_wallet.set(max_wallet);
+        self.max_transfer.set(max_transfer);
+        self.cooldown.set(cooldown);
+    }
+
+    pub fn set_exempt(&mut self, account: Address, exempt: bool) {
+        self.exempt.setter(account).set(exempt);
+    }
+
+    pub fn lock(&mut self) {
+        self.locked.set(true);
+    }
+
+    /// Checks a transfer leaving `to` with `to_balance` against every rule
+    /// and starts the sender's cooldown
+    #[allow(clippy::too_many_arguments)]
+    pub fn enforce(
+        &mut self,
+        token_id: U256,
+        creator: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+        to_balance: U256,
+        now: U256,
+    ) -> Result<(), Vec<u8>> {
+        let from_exempt = from == creator || self.exempt.get(from);
+        let to_exempt = to == creator || self.exempt.get(to);
+
+        let max_transfer = self.max_transfer.get();
+        if max_transfer != U256::ZERO && !from_exempt && !to_exempt && amount > max_transfer {
+            return Err(MaxTransferExceeded { token_id, amount, max_transfer }.abi_encode());
+        }
+
+        let max_wallet = self.max_wallet.get();
+        if max_wallet != U256::ZERO && !to_exempt && to_balance > max_wallet {
+            return Err(MaxWalletExceeded { token_id, account: to, balance: to_balance, max_wallet }.abi_encode());
+        }
+
+        let cooldown = self.cooldown.get();
+        if cooldown != U256::ZERO && !from_exempt {
+            let last = self.last_transfer_at.get(from);
+            let available_at = last + cooldown;
+            if last != U256::ZERO && now < available_at {
+                return Err(TransferCooldown { token_id, account: from, available_at }.abi_encode());
+            }
+            self.last_transfer_at.setter(from).set(now);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! ETH dividends paid pro rata to the holders of a token
+//!
+//! Every distribution raises a magnified dividend-per-share accumulator
+//! instead of crediting each holder. An account's dividends are then
+//! `per_share * balance + correction`, where the correction offsets the
+//! share of earlier distributions that came with tokens it received (or
+//! stays with tokens it sent), so tokens never earn a distribution twice.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{Address, I256, U256},
+    alloy_sol_types::{sol, SolError},
+    prelude::*,
+};
+
+/// Scales the per-share accumulator so small distributions over large supplies keep precision
+const MAGNITUDE: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+sol_storage! {
+    pub struct Dividends {
+        uint256 magnified_per_share;
+        uint256 total_distributed;
+        mapping(address => int256) corrections;
+        mapping(address => uint256) withdrawn;
+    }
+}
+
+sol! {
+    error NothingToDistribute(uint256 token_id);
+    error NothingToWithdraw(uint256 token_id, address account);
+    error DividendTransferFailed(address to, uint256 amount);
+}
+
+impl Dividends {
+    /// Total ETH distributed to the token's holders
+    pub fn total_distributed(&self) -> U256 {
+        self.total_distributed.get()
+    }
+
+    /// Shares `amount` among `total_supply` tokens
+    pub fn distribute(&mut self, token_id: U256, amount: U256, total_supply: U256) -> Result<(), Vec<u8>> {
+        if amount == U256::ZERO || total_supply == U256::ZERO {
+            return Err(NothingToDistribute { token_id }.abi_encode());
+        }
+        let per_share = self.magnified_per_share.get();
+        self.magnified_per_share.set(per_share + amount * MAGNITUDE / total_supply);
+        let total = self.total_distributed.get();
+        self.total_distributed.set(total + amount);
+        Ok(())
+    }
+
+    /// Dividends of an account holding `balance` that it has not withdrawn yet
+    pub fn withdrawable(&self, account: Address, balance: U256) -> U256 {
+        let magnified = I256::from_raw(self.magnified_per_share.get() * balance) + self.corrections.get(account);
+        if magnified.is_negative() {
+            return U256::ZERO;
+        }
+        (magnified.into_raw() / MAGNITUDE).saturating_sub(self.withdrawn.get(account))
+    }
+
+    /// Records `amount` as paid out to `account`
+    pub fn record_withdrawal(&mut self, account: Address, amount: U256) {
+        let withdrawn = self.withdrawn.get(account);
+        self.withdrawn.setter(account).set(withdrawn + amount);
+    }
+
+    /// Keeps past dividends with the sender when `amount` tokens move
+    pub fn on_transfer(&mut self, from: Address, to: Address, amount: U256) {
+        let correction = I256::from_raw(self.magnified_per_share.get() * amount);
+        self._correct(from, correction);
+        self._correct(to, -correction);
+    }
+
+    /// Keeps past dividends from `to` when `amount` tokens are minted to it
+    pub fn on_mint(&mut self, to: Address, amount: U256) {
+        let correction = I256::from_raw(self.magnified_per_share.get() * amount);
+        self._correct(to, -correction);
+    }
+
+    /// Keeps past dividends with `from` when it burns `amount` tokens
+    pub fn on_burn(&mut self, from: Address, amount: U256) {
+        let correction = I256::from_raw(self.magnified_per_share.get() * amount);
+        self._correct(from, correction);
+    }
+
+    fn _correct(&mut self, account: Address, delta: I256) {
+        let correction = self.corrections.get(account);
+        self.corrections.setter(account).set(correction + delta);
+    }
+}
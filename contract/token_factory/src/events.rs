@@ -0,0 +1,55 @@
+//! Events emitted by the TokenFactory
+//!
+//! Kept free of Stylus dependencies so off-chain tools (the registry indexer)
+//! can decode logs with exactly these definitions.
+
+use alloy_sol_types::sol;
+
+// Factory Events
+sol! {
+    event TokenCreated(address indexed creator, uint256 indexed token_id, bytes32 name, bytes32 symbol, uint256 decimals, uint256 initial_supply);
+    event TransferRulesUpdated(uint256 indexed token_id, uint256 max_wallet, uint256 max_transfer, uint256 cooldown);
+    event RuleExemptionSet(uint256 indexed token_id, address indexed account, bool exempt);
+    event TransferRulesLocked(uint256 indexed token_id);
+    event Erc20TemplateSet(address indexed template);
+    event TokenMigrated(uint256 indexed token_id, address indexed token_address, uint256 total_supply);
+    event MigrationClaimed(uint256 indexed token_id, address indexed holder, uint256 amount);
+    event UniqueSymbolsSet(bool enabled);
+    event SymbolReservationSet(bytes32 indexed symbol, bool reserved);
+    event TokenRenamed(uint256 indexed token_id, bytes32 name, bytes32 symbol);
+    event DividendsDistributed(uint256 indexed token_id, address indexed from, uint256 amount);
+    event DividendWithdrawn(uint256 indexed token_id, address indexed to, uint256 amount);
+    event FlashFeeSet(uint256 indexed token_id, uint256 fee_bps);
+    event FlashLoan(uint256 indexed token_id, address indexed receiver, address indexed initiator, uint256 amount, uint256 fee);
+    event L1GatewaySet(address indexed gateway);
+    event WithdrawalInitiated(uint256 indexed token_id, address indexed from, address indexed l1_recipient, uint256 amount, uint256 withdrawal_id);
+    event DepositFinalized(uint256 indexed token_id, address indexed to, uint256 amount);
+    event CreatorTimelockSet(uint256 indexed token_id, uint256 delay);
+    event CreatorCallQueued(uint256 indexed token_id, bytes32 indexed call_id, bytes data, uint256 ready_at);
+    event CreatorCallCancelled(uint256 indexed token_id, bytes32 indexed call_id);
+    event CreatorCallExecuted(uint256 indexed token_id, bytes32 indexed call_id);
+    event CreatorTransferred(uint256 indexed token_id, address indexed previous_creator, address indexed new_creator);
+    event MultisigCreated(address indexed multisig, address[] owners, uint256 threshold);
+    event ProposalCreated(address indexed multisig, uint256 indexed proposal_id, address indexed proposer, bytes data);
+    event ProposalConfirmed(address indexed multisig, uint256 indexed proposal_id, address indexed owner);
+    event ConfirmationRevoked(address indexed multisig, uint256 indexed proposal_id, address indexed owner);
+    event ProposalExecuted(address indexed multisig, uint256 indexed proposal_id);
+    event RebaseOracleSet(uint256 indexed token_id, address indexed oracle);
+    event Rebased(uint256 indexed token_id, uint256 previous_index, uint256 new_index, uint256 total_supply);
+    event Deposit(address indexed to, uint256 amount);
+    event Withdrawal(address indexed from, uint256 amount);
+    event TokenPauseSet(uint256 indexed token_id, bool paused);
+    event SupplyCapSet(uint256 indexed token_id, uint256 cap);
+    event DelegateChanged(uint256 indexed token_id, address indexed delegator, address indexed to_delegate, address from_delegate);
+    event TokenRetired(uint256 indexed token_id, uint256 successor_token_id, address indexed successor);
+    event RedemptionFunded(uint256 indexed token_id, address indexed from, uint256 amount);
+    event TokenRedeemed(uint256 indexed token_id, address indexed holder, uint256 amount);
+}
+
+// ERC20 Events (keyed by token_id since every token lives in the factory).
+// The leading token_id changes topic0 from the plain ERC20 events, so
+// listeners must use these signatures rather than the standard ones.
+sol! {
+    event Transfer(uint256 indexed token_id, address indexed from, address indexed to, uint256 value);
+    event Approval(uint256 indexed token_id, address indexed owner, address indexed spender, uint256 value);
+}
@@ -0,0 +1,258 @@
+//! N-of-M multisigs that can hold a token's creator role
+//!
+//! A multisig lives inside the factory under a derived address nobody holds
+//! a key for. Its owners propose factory calls, confirm them, and once
+//! `threshold` owners confirmed any owner executes the call, which then acts
+//! as the multisig for creator-only checks (and nothing else). Owners and
+//! threshold are fixed at creation.
+//!
+//! Only creator-only calls can be proposed. While one executes it acts as the
+//! multisig for a single creator check, made by the call frame whose calldata
+//! is the proposal's; anything re-entering the factory meanwhile does not.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    alloy_sol_types::{sol, SolCall, SolError},
+    prelude::*,
+};
+
+sol_storage! {
+    pub struct CreatorMultisig {
+        uint256 threshold;  // Zero while the multisig does not exist
+        uint256 owner_count;
+        mapping(uint256 => address) owners;
+        mapping(address => bool) is_owner;
+        uint256 proposal_count;
+        mapping(uint256 => Proposal) proposals;
+    }
+
+    pub struct Proposal {
+        bytes data;  // Factory calldata to execute
+        address proposer;
+        uint256 confirmations;
+        bool executed;
+        mapping(address => bool) confirmed;
+    }
+
+    pub struct RunningProposal {
+        address multisig;  // Multisig whose proposal is executing, zero otherwise
+        bytes32 call_id;  // keccak of the proposal's calldata
+    }
+}
+
+sol! {
+    error InvalidThreshold(uint256 threshold, uint256 owners);
+    error DuplicateOwner(address owner);
+    error UnknownMultisig(address multisig);
+    error NotMultisigOwner(address multisig, address caller);
+    error UnknownProposal(address multisig, uint256 proposal_id);
+    error ProposalAlreadyExecuted(address multisig, uint256 proposal_id);
+    error AlreadyConfirmed(address multisig, uint256 proposal_id, address owner);
+    error NotConfirmed(address multisig, uint256 proposal_id, address owner);
+    error ThresholdNotReached(address multisig, uint256 proposal_id, uint256 confirmations, uint256 threshold);
+    error NotCreatorCall(bytes4 selector);
+}
+
+sol! {
+    /// The factory's creator-only calls, the only ones a multisig can propose
+    interface CreatorCalls {
+        function renameToken(uint256 token_id, bytes32 name, bytes32 symbol);
+        function setTransferRules(uint256 token_id, uint256 max_wallet, uint256 max_transfer, uint256 cooldown);
+        function setRuleExempt(uint256 token_id, address account, bool exempt);
+        function lockTransferRules(uint256 token_id);
+        function migrateToken(uint256 token_id);
+        function setFlashFee(uint256 token_id, uint16 fee_bps);
+        function setCreatorTimelock(uint256 token_id, uint256 delay);
+        function queueCreatorCall(uint256 token_id, bytes data);
+        function cancelCreatorCall(uint256 token_id, bytes32 call_id);
+        function executeCreatorCall(uint256 token_id, bytes data);
+        function mint(uint256 token_id, address to, uint256 amount);
+        function pauseToken(uint256 token_id);
+        function unpauseToken(uint256 token_id);
+        function setSupplyCap(uint256 token_id, uint256 cap);
+        function retireToken(uint256 token_id, uint256 successor_token_id, address successor);
+        function setRebaseOracle(uint256 token_id, address oracle);
+        function rebase(uint256 token_id, uint256 new_index);
+        function transferCreator(uint256 token_id, address new_creator);
+    }
+}
+
+/// Selectors of `CreatorCalls`
+const CREATOR_SELECTORS: [[u8; 4]; 18] = [
+    CreatorCalls::renameTokenCall::SELECTOR,
+    CreatorCalls::setTransferRulesCall::SELECTOR,
+    CreatorCalls::setRuleExemptCall::SELECTOR,
+    CreatorCalls::lockTransferRulesCall::SELECTOR,
+    CreatorCalls::migrateTokenCall::SELECTOR,
+    CreatorCalls::setFlashFeeCall::SELECTOR,
+    CreatorCalls::setCreatorTimelockCall::SELECTOR,
+    CreatorCalls::queueCreatorCallCall::SELECTOR,
+    CreatorCalls::cancelCreatorCallCall::SELECTOR,
+    CreatorCalls::executeCreatorCallCall::SELECTOR,
+    CreatorCalls::mintCall::SELECTOR,
+    CreatorCalls::pauseTokenCall::SELECTOR,
+    CreatorCalls::unpauseTokenCall::SELECTOR,
+    CreatorCalls::setSupplyCapCall::SELECTOR,
+    CreatorCalls::retireTokenCall::SELECTOR,
+    CreatorCalls::setRebaseOracleCall::SELECTOR,
+    CreatorCalls::rebaseCall::SELECTOR,
+    CreatorCalls::transferCreatorCall::SELECTOR,
+];
+
+/// Fails unless `data` calls one of the factory's creator-only functions
+pub fn require_creator_call(data: &[u8]) -> Result<(), Vec<u8>> {
+    let selector: [u8; 4] = data.get(..4).and_then(|selector| selector.try_into().ok()).unwrap_or_default();
+    if !CREATOR_SELECTORS.contains(&selector) {
+        return Err(NotCreatorCall { selector: selector.into() }.abi_encode());
+    }
+    Ok(())
+}
+
+/// (data, proposer, confirmations, executed), as returned by the proposal view
+pub type ProposalInfo = (Vec<u8>, Address, U256, bool);
+
+impl CreatorMultisig {
+    pub fn exists(&self) -> bool {
+        self.threshold.get() != U256::ZERO
+    }
+
+    pub fn threshold(&self) -> U256 {
+        self.threshold.get()
+    }
+
+    pub fn owners(&self) -> Vec<Address> {
+        let count = self.owner_count.get().to::<u64>();
+        (0..count).map(|i| self.owners.get(U256::from(i))).collect()
+    }
+
+    pub fn is_owner(&self, account: Address) -> bool {
+        self.is_owner.get(account)
+    }
+
+    pub fn proposal_count(&self) -> U256 {
+        self.proposal_count.get()
+    }
+
+    /// Sets up a new multisig; `owners` must be distinct and non-zero
+    pub fn init(&mut self, owners: &[Address], threshold: U256) -> Result<(), Vec<u8>> {
+        if threshold == U256::ZERO || threshold > U256::from(owners.len()) {
+            return Err(InvalidThreshold { threshold, owners: U256::from(owners.len()) }.abi_encode());
+        }
+        for (i, &owner) in owners.iter().enumerate() {
+            if owner == Address::ZERO || owners[..i].contains(&owner) {
+                return Err(DuplicateOwner { owner }.abi_encode());
+            }
+        }
+
+        for (i, &owner) in owners.iter().enumerate() {
+            self.owners.setter(U256::from(i)).set(owner);
+            self.is_owner.setter(owner).set(true);
+        }
+        self.owner_count.set(U256::from(owners.len()));
+        self.threshold.set(threshold);
+        Ok(())
+    }
+
+    /// Records a proposal confirmed by its proposer, returning its id
+    pub fn propose(&mut self, proposer: Address, data: &[u8]) -> U256 {
+        let proposal_id = self.proposal_count.get();
+        self.proposal_count.set(proposal_id + U256::from(1));
+
+        let mut proposal = self.proposals.setter(proposal_id);
+        proposal.data.set_bytes(data);
+        proposal.proposer.set(proposer);
+        proposal.confirmations.set(U256::from(1));
+        proposal.confirmed.setter(proposer).set(true);
+        proposal_id
+    }
+
+    pub fn confirm(&mut self, multisig: Address, proposal_id: U256, owner: Address) -> Result<(), Vec<u8>> {
+        self._pending(multisig, proposal_id)?;
+        let mut proposal = self.proposals.setter(proposal_id);
+        if proposal.confirmed.get(owner) {
+            return Err(AlreadyConfirmed { multisig, proposal_id, owner }.abi_encode());
+        }
+        proposal.confirmed.setter(owner).set(true);
+        let confirmations = proposal.confirmations.get();
+        proposal.confirmations.set(confirmations + U256::from(1));
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, multisig: Address, proposal_id: U256, owner: Address) -> Result<(), Vec<u8>> {
+        self._pending(multisig, proposal_id)?;
+        let mut proposal = self.proposals.setter(proposal_id);
+        if !proposal.confirmed.get(owner) {
+            return Err(NotConfirmed { multisig, proposal_id, owner }.abi_encode());
+        }
+        proposal.confirmed.setter(owner).set(false);
+        let confirmations = proposal.confirmations.get();
+        proposal.confirmations.set(confirmations - U256::from(1));
+        Ok(())
+    }
+
+    /// Marks a proposal with enough confirmations as executed, returning its calldata
+    pub fn begin_execution(&mut self, multisig: Address, proposal_id: U256) -> Result<Vec<u8>, Vec<u8>> {
+        self._pending(multisig, proposal_id)?;
+        let threshold = self.threshold.get();
+        let mut proposal = self.proposals.setter(proposal_id);
+        let confirmations = proposal.confirmations.get();
+        if confirmations < threshold {
+            return Err(ThresholdNotReached { multisig, proposal_id, confirmations, threshold }.abi_encode());
+        }
+        proposal.executed.set(true);
+        Ok(proposal.data.get_bytes())
+    }
+
+    pub fn proposal(&self, proposal_id: U256) -> ProposalInfo {
+        let proposal = self.proposals.getter(proposal_id);
+        (proposal.data.get_bytes(), proposal.proposer.get(), proposal.confirmations.get(), proposal.executed.get())
+    }
+
+    pub fn has_confirmed(&self, proposal_id: U256, owner: Address) -> bool {
+        self.proposals.getter(proposal_id).confirmed.get(owner)
+    }
+
+    /// Ids of the proposals not executed yet
+    pub fn pending_proposals(&self) -> Vec<U256> {
+        let count = self.proposal_count.get().to::<u64>();
+        (0..count)
+            .map(U256::from)
+            .filter(|&id| !self.proposals.getter(id).executed.get())
+            .collect()
+    }
+
+    /// Fails unless the proposal exists and has not been executed
+    fn _pending(&self, multisig: Address, proposal_id: U256) -> Result<(), Vec<u8>> {
+        if proposal_id >= self.proposal_count.get() {
+            return Err(UnknownProposal { multisig, proposal_id }.abi_encode());
+        }
+        if self.proposals.getter(proposal_id).executed.get() {
+            return Err(ProposalAlreadyExecuted { multisig, proposal_id }.abi_encode());
+        }
+        Ok(())
+    }
+}
+
+impl RunningProposal {
+    /// Marks `multisig`'s proposal with calldata hashing to `call_id` as running
+    pub fn begin(&mut self, multisig: Address, call_id: B256) {
+        self.multisig.set(multisig);
+        self.call_id.set(call_id);
+    }
+
+    /// Lets the running proposal act as `creator` once, if the call with id `call_id` is it
+    pub fn take(&mut self, creator: Address, call_id: B256) -> bool {
+        if self.multisig.get() != creator || self.call_id.get() != call_id {
+            return false;
+        }
+        self.end();
+        true
+    }
+
+    /// Marks the running proposal as done
+    pub fn end(&mut self) {
+        self.multisig.set(Address::ZERO);
+        self.call_id.set(B256::ZERO);
+    }
+}
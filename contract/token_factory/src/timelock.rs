@@ -0,0 +1,120 @@
+//! Opt-in delay on a token's creator-only calls
+//!
+//! Once a creator sets a delay, their calls (fee changes, transfer rules,
+//! renames, migration) can no longer be made directly: they are queued as
+//! factory calldata, executed by the creator once the delay has passed, or
+//! cancelled before. Holders see every queued change coming, and the delay
+//! can only ever be increased.
+//!
+//! A running queued call authorizes exactly one creator check, made by the
+//! call frame whose calldata is the queued calldata; anything re-entering
+//! the factory meanwhile still finds the timelock in place.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{B256, U256},
+    alloy_sol_types::{sol, SolError},
+    crypto::keccak,
+    prelude::*,
+};
+
+sol_storage! {
+    pub struct CreatorTimelock {
+        uint256 delay;  // Seconds, zero while the token has no timelock
+        mapping(bytes32 => uint256) queued;  // keccak(calldata) -> Timestamp it may execute at
+        bytes32 executing;  // Id of the queued call running, zero otherwise
+    }
+}
+
+sol! {
+    error TimelockRequired(uint256 token_id);
+    error TimelockNotSet(uint256 token_id);
+    error TimelockDelayDecrease(uint256 token_id, uint256 delay);
+    error CallAlreadyQueued(uint256 token_id, bytes32 call_id);
+    error CallNotQueued(uint256 token_id, bytes32 call_id);
+    error CallNotReady(uint256 token_id, bytes32 call_id, uint256 ready_at);
+}
+
+/// Identifies a queued call
+pub fn call_id(data: &[u8]) -> B256 {
+    keccak(data)
+}
+
+impl CreatorTimelock {
+    pub fn delay(&self) -> U256 {
+        self.delay.get()
+    }
+
+    /// Timestamp a queued call may execute at, zero if it is not queued
+    pub fn ready_at(&self, call_id: B256) -> U256 {
+        self.queued.get(call_id)
+    }
+
+    /// Fails if the token has a timelock
+    pub fn check(&self, token_id: U256) -> Result<(), Vec<u8>> {
+        if self.delay.get() != U256::ZERO {
+            return Err(TimelockRequired { token_id }.abi_encode());
+        }
+        Ok(())
+    }
+
+    /// Takes the running call if the call with id `call_id` is it, so it passes only once
+    pub fn take(&mut self, call_id: B256) -> bool {
+        if self.executing.get() != call_id {
+            return false;
+        }
+        self.executing.set(B256::ZERO);
+        true
+    }
+
+    /// Sets the delay, which may only grow
+    pub fn set_delay(&mut self, token_id: U256, delay: U256) -> Result<(), Vec<u8>> {
+        let current = self.delay.get();
+        if delay < current {
+            return Err(TimelockDelayDecrease { token_id, delay: current }.abi_encode());
+        }
+        self.delay.set(delay);
+        Ok(())
+    }
+
+    /// Queues a call to run `delay` seconds after `now`, returning when
+    pub fn queue(&mut self, token_id: U256, call_id: B256, now: U256) -> Result<U256, Vec<u8>> {
+        if self.delay.get() == U256::ZERO {
+            return Err(TimelockNotSet { token_id }.abi_encode());
+        }
+        if self.queued.get(call_id) != U256::ZERO {
+            return Err(CallAlreadyQueued { token_id, call_id }.abi_encode());
+        }
+        let ready_at = now + self.delay.get();
+        self.queued.setter(call_id).set(ready_at);
+        Ok(ready_at)
+    }
+
+    /// Removes a queued call
+    pub fn cancel(&mut self, token_id: U256, call_id: B256) -> Result<(), Vec<u8>> {
+        if self.queued.get(call_id) == U256::ZERO {
+            return Err(CallNotQueued { token_id, call_id }.abi_encode());
+        }
+        self.queued.setter(call_id).set(U256::ZERO);
+        Ok(())
+    }
+
+    /// Dequeues a call whose delay has passed and marks it as running
+    pub fn begin(&mut self, token_id: U256, call_id: B256, now: U256) -> Result<(), Vec<u8>> {
+        let ready_at = self.queued.get(call_id);
+        if ready_at == U256::ZERO {
+            return Err(CallNotQueued { token_id, call_id }.abi_encode());
+        }
+        if now < ready_at {
+            return Err(CallNotReady { token_id, call_id, ready_at }.abi_encode());
+        }
+        self.queued.setter(call_id).set(U256::ZERO);
+        self.executing.set(call_id);
+        Ok(())
+    }
+
+    /// Marks the running call as done
+    pub fn end(&mut self) {
+        self.executing.set(B256::ZERO);
+    }
+}
@@ -0,0 +1,117 @@
+//! Vote delegation with historical lookups, for tokens created with `FEATURE_VOTES`
+//!
+//! As in ERC20Votes, balances only count once delegated (an account may
+//! delegate to itself). Every change to a delegate's votes is checkpointed
+//! with the block timestamp, so governance can read the votes an account
+//! had at any past time. Checkpoints hold shares, which equal tokens unless
+//! the token rebases; the factory reports votes in tokens at the current
+//! rebase index. A rebase scales every account alike, so the votes accounts
+//! had against each other at a past time stay as they were.
+
+use alloc::{vec, vec::Vec};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    alloy_sol_types::{sol, SolError},
+    prelude::*,
+};
+
+sol_storage! {
+    pub struct Votes {
+        mapping(address => address) delegates;
+        mapping(address => Checkpoints) checkpoints;
+    }
+
+    /// A delegate's votes over time, oldest first
+    pub struct Checkpoints {
+        uint256 count;
+        mapping(uint256 => uint256) timepoints;
+        mapping(uint256 => uint256) votes;
+    }
+}
+
+sol! {
+    error FutureLookup(uint256 timepoint, uint256 now);
+}
+
+impl Votes {
+    /// The account `account` delegates its votes to, zero if none
+    pub fn delegates(&self, account: Address) -> Address {
+        self.delegates.get(account)
+    }
+
+    /// Current votes of `account`
+    pub fn get_votes(&self, account: Address) -> U256 {
+        let checkpoints = self.checkpoints.getter(account);
+        let count = checkpoints.count.get();
+        if count == U256::ZERO {
+            return U256::ZERO;
+        }
+        checkpoints.votes.get(count - U256::from(1))
+    }
+
+    /// Votes of `account` at the end of `timepoint`, which must be in the past
+    pub fn get_past_votes(&self, account: Address, timepoint: U256, now: U256) -> Result<U256, Vec<u8>> {
+        if timepoint >= now {
+            return Err(FutureLookup { timepoint, now }.abi_encode());
+        }
+
+        // Last checkpoint at or before `timepoint`
+        let checkpoints = self.checkpoints.getter(account);
+        let (mut low, mut high) = (U256::ZERO, checkpoints.count.get());
+        while low < high {
+            let mid = (low + high) / U256::from(2);
+            if checkpoints.timepoints.get(mid) > timepoint {
+                high = mid;
+            } else {
+                low = mid + U256::from(1);
+            }
+        }
+        if high == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        Ok(checkpoints.votes.get(high - U256::from(1)))
+    }
+
+    /// Points the votes of `account`, which holds `balance`, at `delegatee`
+    /// Returns the previous delegate.
+    pub fn delegate(&mut self, account: Address, delegatee: Address, balance: U256, now: U256) -> Address {
+        let previous = self.delegates.get(account);
+        self.delegates.setter(account).set(delegatee);
+        self._move_votes(previous, delegatee, balance, now);
+        previous
+    }
+
+    /// Moves the votes behind `amount` along with a transfer (zero `from` / `to` for mints and burns)
+    pub fn on_transfer(&mut self, from: Address, to: Address, amount: U256, now: U256) {
+        let from_delegate = if from == Address::ZERO { Address::ZERO } else { self.delegates.get(from) };
+        let to_delegate = if to == Address::ZERO { Address::ZERO } else { self.delegates.get(to) };
+        self._move_votes(from_delegate, to_delegate, amount, now);
+    }
+
+    fn _move_votes(&mut self, from: Address, to: Address, amount: U256, now: U256) {
+        if from == to || amount == U256::ZERO {
+            return;
+        }
+        if from != Address::ZERO {
+            let votes = self.get_votes(from);
+            self._checkpoint(from, votes - amount, now);
+        }
+        if to != Address::ZERO {
+            let votes = self.get_votes(to);
+            self._checkpoint(to, votes + amount, now);
+        }
+    }
+
+    /// Records `votes` for `account`, overwriting a checkpoint from the same timestamp
+    fn _checkpoint(&mut self, account: Address, votes: U256, now: U256) {
+        let mut checkpoints = self.checkpoints.setter(account);
+        let count = checkpoints.count.get();
+        if count != U256::ZERO && checkpoints.timepoints.get(count - U256::from(1)) == now {
+            checkpoints.votes.setter(count - U256::from(1)).set(votes);
+            return;
+        }
+        checkpoints.timepoints.setter(count).set(now);
+        checkpoints.votes.setter(count).set(votes);
+        checkpoints.count.set(count + U256::from(1));
+    }
+}